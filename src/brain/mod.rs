@@ -1,19 +1,24 @@
 use std::collections::HashSet;
-use std::path::{Path};
-use std::time::{Instant, Duration};
-use std::thread;
+use std::path::Path;
 use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, UNIX_EPOCH, Instant};
 
 use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg64;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json;
 
+use crate::controller::Controller;
+use crate::network::NeuralNetwork;
 use crate::neurone::NeuroneWebAction;
-use crate::params::{GameParameters, self};
-use crate::utils::{remove_indexes, get_max_i};
-use crate::{neurone::NeuroneWeb, entity::Obstacle, utils::str_to_u8_array, game::Game};
+use crate::params::{GameParameters, CrossoverType, ControllerType, TrainingConfig};
+use crate::error::{Error, Result};
+use crate::selection::{compare_brain_score, dedup_selected, get_selected_indexes};
+use crate::species::{Speciation, SpeciesParents, SavedSpeciesParents};
+use crate::stats::GenerationStats;
+use crate::utils::{remove_indexes, get_brain_indexes, uniform_crossover, merge_crossover, write_json};
+use crate::{neurone::NeuroneWeb, entity::{Dinosaur, Obstacle}, utils::str_to_u8_array, race::Race};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Brain {
@@ -59,11 +64,24 @@ impl Brain {
         }
     }
 
+    /// recombine two brains and return a new brain (see CrossoverType)
+    pub fn crossover(&self, other : &Brain, params : &GameParameters, rng : &mut Pcg64) -> Self {
+        let neurone_web = match params.crossover_type {
+            CrossoverType::Web => uniform_crossover(&self.neurone_web, &other.neurone_web, rng),
+            CrossoverType::Neurone => merge_crossover(&self.neurone_web, &other.neurone_web, rng, |web_a, web_b, rng| web_a.crossover(web_b, rng)),
+        };
+
+        Self {
+            neurone_web
+        }
+    }
+
     /// get the actions of the brain
-    pub fn get_activations(&self, obstacles : &Vec<Obstacle>) -> HashSet<NeuroneWebAction> {
+    /// NOTE : the dinosaur is needed for the neurones anchored to it and the neurones on its state, the speed for the fast game neurones
+    pub fn get_activations(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> HashSet<NeuroneWebAction> {
         let mut activations = HashSet::new();
         for neurone_web in &self.neurone_web {
-            if neurone_web.is_activated(obstacles) {
+            if neurone_web.is_activated(obstacles, dinosaur, speed) {
                 activations.insert(neurone_web.action.clone());
             }
         }
@@ -81,48 +99,142 @@ impl Brain {
         energie
     }
 
-    /// mutate a vect of brain into a number of brain
-    /// genere the next generation (mutate all the best brains, begin randomly)
-    /// keep all the best brains and doesn't discard them
-    /// take a random brain from the best brains and rotate the index
-    pub fn mutate_all(params : &GameParameters, brains : &Vec<Brain>, rng : &mut Pcg64) -> Vec<Brain> {
-        let mut i = rng.gen_range(0..brains.len());
-        let mut next_brains = Vec::new();
-        for _ in 0..params.training_nb_brain {
-            // mutate the brain
-            let brain = brains[i].mutate(params, rng);
-            next_brains.push(brain);
-            // rotate the best scores index
-            i = i + 1;
-            if i >= brains.len() {
-                i = 0;
-            }
+    /// get the number of neurone of all the neurone webs
+    pub fn get_nb_neurone(&self) -> usize {
+        self.neurone_web.iter().map(|neurone_web| neurone_web.neurones.len()).sum()
+    }
+
+    /// get the compatibility distance with another brain (for the speciation)
+    /// NOTE : the webs are compared by position in the brain (like the neurone crossover), a web without pair counts as a full difference
+    pub fn get_distance(&self, other : &Brain, params : &GameParameters) -> f64 {
+        let nb_web = self.neurone_web.len().max(other.neurone_web.len());
+        if nb_web == 0 {
+            return 0.0;
         }
+        let unmatched_distance = self.neurone_web.len().abs_diff(other.neurone_web.len()) as f64 * params.speciation_web_coefficient;
+        let matched_distance : f64 = self.neurone_web.iter()
+            .zip(&other.neurone_web)
+            .map(|(web, other_web)| web.get_distance(other_web, params))
+            .sum();
+        (unmatched_distance + matched_distance) / nb_web as f64
+    }
+}
+
+impl Controller for Brain {
+    fn new_random(params : &GameParameters, rng : &mut Pcg64) -> Self {
+        Brain::new(params, rng)
+    }
+
+    fn mutate(&self, params : &GameParameters, rng : &mut Pcg64) -> Self {
+        Brain::mutate(self, params, rng)
+    }
+
+    fn crossover(&self, other : &Self, params : &GameParameters, rng : &mut Pcg64) -> Self {
+        Brain::crossover(self, other, params, rng)
+    }
 
-        next_brains
+    fn get_activations(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> HashSet<NeuroneWebAction> {
+        Brain::get_activations(self, obstacles, dinosaur, speed)
+    }
+
+    fn get_energie(&self, params : &GameParameters) -> f64 {
+        Brain::get_energie(self, params)
+    }
+
+    /// the number of neurone webs and of neurones
+    fn get_size(&self) -> (usize, usize) {
+        (self.neurone_web.len(), self.get_nb_neurone())
+    }
+
+    fn get_distance(&self, other : &Self, params : &GameParameters) -> f64 {
+        Brain::get_distance(self, other, params)
     }
 }
 
 
-fn brain_run(params : &GameParameters, brain : Brain, seed : &str) -> u64 {
-    // create the game
-    let mut now = Instant::now();
-    let mut game = Game::new(params, now, seed, Some(brain), None);
-    let interval = 1000_000_000 / params.game_fps as u64;// in nanoseconds
-    let duration = Duration::from_nanos(interval);
-    // run the game
-    while !game.has_lost && (game.score < params::LIMIT_SCORE) {
-        now = now.checked_add(duration).unwrap();
-        game.update(now);
+/// a brain (or another controller) with its fitness on the terrains of the generation
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrainScore<C = Brain> {
+    pub brain : C,
+    /// the aggregated fitness (see FitnessAggregation)
+    pub score : f64,
+    /// the score on each terrain (same order as the seeds)
+    pub scores : Vec<u64>,
+    /// the energie of the brain (computed once for the comparisons, see compare_brain_score)
+    pub energy : f64,
+}
+
+impl<C : Controller> BrainScore<C> {
+    /// score the brain with its scores on the terrains
+    pub fn new(params : &GameParameters, brain : C, scores : Vec<u64>) -> Self {
+        Self {
+            score : params.fitness_aggregation.aggregate(&scores),
+            energy : brain.get_energie(params),
+            brain,
+            scores,
+        }
+    }
+}
+
+#[cfg(test)]
+impl BrainScore {
+    /// a scored brain with the given fitness on one terrain (for the tests)
+    pub fn new_test(params : &GameParameters, brain : Brain, score : f64) -> Self {
+        Self {
+            energy : brain.get_energie(params),
+            brain,
+            score,
+            scores : vec![score as u64],
+        }
     }
 
-    game.score
+    /// random scored brains with the given fitness (for the tests)
+    pub fn new_random_tests(params : &GameParameters, scores : &[f64]) -> Vec<Self> {
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        scores.iter().map(|score| Self::new_test(params, Brain::new(params, &mut rng), *score)).collect()
+    }
 }
 
-/// generate the next generation
-fn generate_next_generation(params : &GameParameters, ancestor : &Vec<Brain>, rng : &mut Pcg64) -> Vec<Brain> {
-    let mut next_generation = Brain::mutate_all(params, &ancestor.iter().map(|brain| brain.clone()).collect(), rng);
-    // add the old best brains randomly
+/// run all the brains of a generation on every seed on the worker pool
+/// NOTE : the brains race by chunk on each seed (one chunk per worker), so the obstacles are simulated once per race
+/// NOTE : the scores are in the same order as the brains (same result as a sequential run)
+fn evaluate_generation<C : Controller>(pool : &ThreadPool, params : &GameParameters, training : &TrainingConfig, brains : Vec<C>, seeds : &[String]) -> Vec<BrainScore<C>> {
+    let chunk_size = brains.len().div_ceil(pool.current_num_threads()).max(1);
+    let chunks : Vec<&[C]> = brains.chunks(chunk_size).collect();
+    let race_scores : Vec<Vec<u64>> = pool.install(|| {
+        seeds
+            .par_iter()
+            .flat_map_iter(|seed| chunks.iter().map(move |chunk| (seed, chunk)))
+            .map(|(seed, chunk)| {
+                let mut race = Race::new(params, seed, chunk.to_vec());
+                race.run(Some(training.limit_score));
+                race.get_scores()
+            })
+            .collect()
+    });
+    // the scores of all the brains on each seed
+    let seed_scores : Vec<Vec<u64>> = race_scores
+        .chunks(chunks.len().max(1))
+        .map(|races| races.concat())
+        .collect();
+
+    brains
+        .into_iter()
+        .enumerate()
+        .map(|(i, brain)| BrainScore::new(params, brain, seed_scores.iter().map(|scores| scores[i]).collect()))
+        .collect()
+}
+
+/// generate the next generation (the children of the parents, then the old best brains)
+fn generate_next_generation<C : Controller>(params : &GameParameters, parents : &[C], old_best_brains : &[C], rng : &mut Pcg64) -> Vec<C> {
+    let mut next_generation = C::mutate_all(params, parents, params.training_nb_brain, rng);
+    add_old_best_brains(params, old_best_brains, &mut next_generation, rng);
+    // update the next generation
+    next_generation
+}
+
+/// add the old best brains randomly to the next generation (all of them, or max_nb_brain_to_save)
+fn add_old_best_brains<C : Controller>(params : &GameParameters, ancestor : &[C], next_generation : &mut Vec<C>, rng : &mut Pcg64) {
     if params.max_nb_brain_to_save < 0 || ancestor.len() <= params.max_nb_brain_to_save as usize{
         for best_brain in ancestor {
             next_generation.push(best_brain.clone());
@@ -141,48 +253,143 @@ fn generate_next_generation(params : &GameParameters, ancestor : &Vec<Brain>, rn
             
         }
     }
-    // update the next generation
+}
+
+/// generate the next generation species by species (the children of the parents of each species), then add the old best brains
+fn generate_next_generation_by_species<C : Controller>(params : &GameParameters, species_parents : &[SpeciesParents<C>], best_brains : &[&BrainScore<C>], rng : &mut Pcg64) -> Vec<C> {
+    let mut next_generation = Vec::new();
+    for species in species_parents {
+        let parents : Vec<C> = species.parents.iter().map(|score| score.brain.clone()).collect();
+        next_generation.extend(C::mutate_all(params, &parents, species.nb_children, rng));
+    }
+    add_old_best_brains(params, &get_brains(&dedup_selected(best_brains)), &mut next_generation, rng);
+
     next_generation
 }
 
-/// wrapper
-fn generate_next_generation_from_scoring(params : &GameParameters, ancestor : &Vec<&(Brain, u64)>, rng : &mut Pcg64)-> Vec<Brain> {
-    generate_next_generation(params, &ancestor.iter().map(|&(brain, _)| brain.clone()).collect(), rng)
+/// wrapper (the brains selected several times are only added once as old best brains)
+fn generate_next_generation_from_scoring<C : Controller>(params : &GameParameters, ancestor : &[&BrainScore<C>], rng : &mut Pcg64)-> Vec<C> {
+    generate_next_generation(params, &get_brains(ancestor), &get_brains(&dedup_selected(ancestor)), rng)
+}
+
+/// get the brains of the scored brains
+fn get_brains<C : Controller>(scores : &[&BrainScore<C>]) -> Vec<C> {
+    scores.iter().map(|score| score.brain.clone()).collect()
 }
 
-// generate the seed for the actual generation i
-fn generate_seed(params : &GameParameters, i : u64, prec_seed : &str, rng : &mut Pcg64) -> String {
+// generate the seeds (one per terrain) for the actual generation i
+fn generate_seeds(params : &GameParameters, i : u64, prec_seeds : &[String], rng : &mut Pcg64) -> Vec<String> {
     match params.terrain_seed_generation_interval {
         Some(interval) => {
-            if i % interval == 0 {
-                rng
-                    .sample_iter(rand::distributions::Alphanumeric)
-                    .take(8)
-                    .map(char::from)
+            if i.is_multiple_of(interval) || prec_seeds.is_empty() {
+                (0..params.training_nb_terrain)
+                    .map(|_| rng
+                        .sample_iter(rand::distributions::Alphanumeric)
+                        .take(8)
+                        .map(char::from)
+                        .collect())
                     .collect()
             }else{
-                prec_seed.to_string()
+                prec_seeds.to_vec()
             }
         },
         None => {
-            params.land_seed.as_str().to_string()
+            // the first terrain is the param land seed, the others are derived from it
+            (0..params.training_nb_terrain)
+                .map(|k| if k == 0 {
+                    params.land_seed.clone()
+                }else{
+                    format!("{}{}", params.land_seed, k)
+                })
+                .collect()
         }
     }
 }
 
+/// a brain file : the best brains (or other controllers) of a generation, with the rng to resume the training
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct IntermediateResult {
-    pub brains : Vec<Brain>,
+pub struct IntermediateResult<C = Brain> {
+    pub brains : Vec<C>,
     pub rng : Pcg64,
-    pub score : u64,
+    /// the aggregated fitness of the best brains
+    pub score : f64,
+    /// the seeds of the terrains played by the brains
+    #[serde(default)]
+    pub seeds : Vec<String>,
+    /// the score of each brain on each terrain (same order as brains and seeds)
+    #[serde(default)]
+    pub scores : Vec<Vec<u64>>,
+    /// the parents of the next generation (index in brains, a brain selected several times is repeated)
+    /// NOTE : without parents (an old brain file), each brain is a parent once
+    #[serde(default)]
+    pub parents : Vec<usize>,
+    /// the species of the training after the selection (None without speciation, or in an old brain file)
+    /// NOTE : a default function, #[serde(default)] would ask the controller to implement Default
+    #[serde(default = "Option::default")]
+    pub speciation : Option<Speciation<C>>,
+    /// the parents chosen in each species, with their number of children
+    #[serde(default)]
+    pub species_parents : Vec<SavedSpeciesParents>,
+}
+
+impl<C : Controller> IntermediateResult<C> {
+    /// load a brain file
+    /// NOTE : a brain file without brain is an error
+    pub fn new_from_file(path : &str) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|source| Error::Read { path : path.to_string(), source })?;
+        let result : IntermediateResult<C> = serde_json::from_str(&content)
+            .map_err(|source| Error::CorruptCheckpoint { path : path.to_string(), source })?;
+        if result.brains.is_empty() {
+            return Err(Error::EmptyCheckpoint { path : path.to_string() });
+        }
+
+        Ok(result)
+    }
+
+    /// get the saved brains with their saved scores (to speciate them again when the training is resumed)
+    /// NOTE : a brain without saved scores (an old brain file) has a null score
+    pub fn get_brain_scores(&self, params : &GameParameters) -> Vec<BrainScore<C>> {
+        self.brains.iter()
+            .enumerate()
+            .map(|(i, brain)| BrainScore::new(params, brain.clone(), self.scores.get(i).cloned().unwrap_or_default()))
+            .collect()
+    }
+
+    /// get the parents of the next generation (with the brains selected several times)
+    pub fn get_parents(&self) -> Vec<C> {
+        if self.parents.is_empty() {
+            return self.brains.clone();
+        }
+        self.parents.iter().map(|i| self.brains[*i].clone()).collect()
+    }
 }
 
-/// train the brain
-pub fn brain_train_pipeline(folder_path_input : Option<String>){
+/// load the last valid brain file of the training folder (with its index)
+/// NOTE : an invalid brain file is skipped (with a warning) and the previous one is loaded
+fn load_last_result<C : Controller>(folder_path : &str) -> Result<(u64, IntermediateResult<C>)> {
+    for i in get_brain_indexes(folder_path)?.into_iter().rev() {
+        let brain_path = format!("{}brain{}.json", folder_path, i);
+        match IntermediateResult::new_from_file(&brain_path) {
+            Ok(result) => return Ok((i, result)),
+            Err(error) => println!("Warning : {}, the previous brain file is used", error),
+        }
+    }
+
+    Err(Error::NoCheckpoint { path : folder_path.to_string() })
+}
+
+/// train the brains (or the neural networks, see ControllerType)
+/// NOTE : the params given by the user are used to create a new training, on resume they must be the same as the params of the folder
+/// NOTE : the training config is saved in the folder (training.json) and reloaded on resume,
+/// unless a new one is given by the user
+/// NOTE : the number of workers is a setting of the machine, it isn't saved with the params (None : one worker per cpu)
+pub fn brain_train_pipeline(folder_path_input : Option<String>, params_path : Option<String>, training_path : Option<String>, nb_worker : Option<usize>) -> Result<()> {
+    let input_params = params_path.map(|path| GameParameters::new_from_file(&path)).transpose()?;
+    let input_training = training_path.map(|path| TrainingConfig::new_from_file(&path)).transpose()?;
     // ----------- create the folder where we will save the brains (or load it) ------------
     let mut folder_path;
-    if folder_path_input.is_some() {
-        folder_path = folder_path_input.unwrap();
+    if let Some(folder_path_input) = folder_path_input {
+        folder_path = folder_path_input;
     }else{
         // Get the current timestamp
         let timestamp = SystemTime::now()
@@ -192,7 +399,10 @@ pub fn brain_train_pipeline(folder_path_input : Option<String>){
 
         // Convert the timestamp to a string
         let timestamp = timestamp.to_string();
-        let result_folder = params::RESULT_FOLDER_PATH.clone();
+        let result_folder = match &input_training {
+            Some(training) => training.result_folder_path.clone(),
+            None => TrainingConfig::new_default().result_folder_path,
+        };
         folder_path = format!("{}{}", result_folder, timestamp);
     }
     // check the slash at the end
@@ -200,124 +410,372 @@ pub fn brain_train_pipeline(folder_path_input : Option<String>){
         folder_path = format!("{}/", folder_path);
     }
 
-    let mut rng;
-    let mut brains;
-    let i_begin;
     let params; 
+    let training;
+    let resume;
 
     // Create the folder if it doesn't exist
     if !Path::new(&folder_path).exists() {
-        params = GameParameters::new_default();
+        params = input_params.unwrap_or_else(GameParameters::new_default);
         fs::create_dir(folder_path.clone())
-            .expect("Failed to create folder");
+            .map_err(|source| Error::Write { path : folder_path.clone(), source })?;
 
         // save the params
-        let params_str = serde_json::to_string(&params).unwrap();
         let params_path = format!("{}params.json", folder_path.clone());
-        fs::write(params_path, params_str).expect("Unable to write file");
+        write_json(&params_path, &params)?;
 
+        training = input_training.unwrap_or_else(TrainingConfig::new_default);
+        resume = false;
+    }else{
+        // try to load the params and compare it to the current params
+        let params_path = format!("{}params.json", folder_path.clone());
+        if !Path::new(&params_path).exists() {
+            return Err(Error::MissingRunFolder { path : folder_path });
+        }
+        params = GameParameters::new_from_file(&params_path)?;
+        // refuse to resume with other params than the ones of the folder
+        if let Some(input_params) = input_params {
+            let differences = params.diff(&input_params);
+            if !differences.is_empty() {
+                return Err(Error::ParamsMismatch { path : params_path, differences });
+            }
+        }
+
+        // the given training config replace the one of the folder
+        let training_path = format!("{}training.json", folder_path.clone());
+        training = match input_training {
+            Some(training) => {
+                println!("The training config of the folder is replaced by the given one");
+                training
+            },
+            None if Path::new(&training_path).exists() => TrainingConfig::new_from_file(&training_path)?,
+            None => TrainingConfig::new_default(),
+        };
+        resume = true;
+    }
+
+    // the training is the same for every controller
+    match params.controller {
+        ControllerType::NeuroneWeb => train::<Brain>(&folder_path, &params, &training, resume, nb_worker),
+        ControllerType::NeuralNetwork => train::<NeuralNetwork>(&folder_path, &params, &training, resume, nb_worker),
+    }
+}
+
+/// run the generations of the training in the folder (from new, or continue from the last brain file)
+fn train<C : Controller>(folder_path : &str, params : &GameParameters, training : &TrainingConfig, resume : bool, nb_worker : Option<usize>) -> Result<()> {
+    let selection = params.selection.get_selection(training);
+    let mut speciation = params.speciation_threshold.map(Speciation::new);
+    let mut rng;
+    let mut brains;
+    let i_begin;
+    // the seeds of the saved generation (kept by the next one if it isn't time to generate new seeds)
+    let saved_seeds;
+    if resume {
+        // get the last brain
+        let (max_i, result) = load_last_result::<C>(folder_path)?;
+
+        rng = result.rng.clone();
+        brains = match &mut speciation {
+            Some(speciation) => {
+                let parents = result.get_brain_scores(params);
+                let species_parents = match &result.speciation {
+                    // the saved species continue as if the training wasn't interrupted
+                    Some(saved_speciation) => {
+                        *speciation = saved_speciation.clone();
+                        result.species_parents.iter().map(|species| species.to_species_parents(&parents)).collect()
+                    },
+                    // an old brain file : the saved parents are speciated again to share the children between their species
+                    None => speciation.select(params, &*selection, &parents, &mut rng),
+                };
+                let best_parents : Vec<&BrainScore<C>> = parents.iter().collect();
+                generate_next_generation_by_species(params, &species_parents, &best_parents, &mut rng)
+            },
+            None => generate_next_generation(params, &result.get_parents(), &result.brains, &mut rng),
+        };
+
+        i_begin = max_i + 1;
+        saved_seeds = result.seeds;
+        // the generations after the brain file are run again
+        GenerationStats::truncate(folder_path, i_begin)?;
+    }else{
         rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
 
         // create a lot of brain
         brains = Vec::new();
         for _ in 0..params.training_nb_brain {
-            brains.push(Brain::new(&params, &mut rng));
+            brains.push(C::new_random(params, &mut rng));
         }
 
         i_begin = 0;
-    }else{
-        // try to load the params and compare it to the current params
-        let params_path = format!("{}params.json", folder_path.clone());
-        params = GameParameters::new_from_file(&params_path);
+        saved_seeds = Vec::new();
+    }
 
-        // get the last brain
-        let max_i = get_max_i(&folder_path).expect("Unable to get the max i");
-        let brain_path = format!("{}brain{}.json", folder_path.clone(), max_i);
-        let result : IntermediateResult = serde_json::from_str(&fs::read_to_string(brain_path).expect("Unable to read file of result")).unwrap();
-        
-        rng = result.rng;
-        brains = generate_next_generation(&params, &result.brains, &mut rng);
+    // save the training config
+    let training_path = format!("{}training.json", folder_path);
+    write_json(&training_path, training)?;
 
-        i_begin = max_i + 1;
-    }
-    
-    
 
     // ----------- run the brains ------------
-    
-    let mut land_seed = generate_seed(&params, 0, params.land_seed.as_str(), &mut rng);
-    for i in i_begin..(i_begin + params::TRAINING_NB_GENERATION) {
+    // create the worker pool (0 thread let rayon use one worker per cpu)
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(nb_worker.unwrap_or(0))
+        .build()
+        .expect("Failed to create the worker pool");
+
+    let mut land_seeds = generate_seeds(params, i_begin, &saved_seeds, &mut rng);
+    for i in i_begin..(i_begin + training.nb_generation) {
         // run the brains in parallel
-        println!("land seed : {}", land_seed);
-        let mut scores : Vec<(Brain, u64)> = Vec::new();
-        let mut handles = vec![];
-        for brain in brains {
-            let brain_copy = brain.clone();
-            let seed_copy = land_seed.clone();
-            let params_copy = params.clone();
-            let handle = thread::spawn(move || {
-                let result = brain_run(&params_copy, brain_copy, seed_copy.as_str());
-                (brain, result)
-            });
-            handles.push(handle);
+        println!("land seeds : {}", land_seeds.join(", "));
+        let generation_start = Instant::now();
+        let scores = evaluate_generation(&pool, params, training, brains, &land_seeds);
+
+        // ----------------- get the best brains (in each species with the speciation) -----------------
+        let species_parents = speciation.as_mut().map(|speciation| speciation.select(params, &*selection, &scores, &mut rng));
+        let best_brains = match &species_parents {
+            Some(species_parents) => {
+                let mut best_brains : Vec<&BrainScore<C>> = species_parents.iter().flat_map(|species| &species.parents).collect();
+                best_brains.sort_by(|a, b| compare_brain_score(a, b));
+                best_brains
+            },
+            None => selection.select(params, &scores, &mut rng),
+        };
+
+        // -------------------- save the progression (brain and random) -------------------------
+        if i.is_multiple_of(training.interval_to_save_result) || i == i_begin + training.nb_generation - 1{
+            let brains_to_save = dedup_selected(&best_brains);
+            let to_save = IntermediateResult {
+                brains : get_brains(&brains_to_save),
+                rng : rng.clone(),
+                score : brains_to_save[0].score,
+                seeds : land_seeds.clone(),
+                scores : brains_to_save.iter().map(|score| score.scores.clone()).collect(),
+                parents : get_selected_indexes(&best_brains, &brains_to_save),
+                speciation : speciation.clone(),
+                species_parents : species_parents.iter().flatten().map(|species| species.to_saved(&brains_to_save)).collect(),
+            };
+            // save the best brains
+            let brain_path = format!("{}brain{}.json", folder_path, i);
+            write_json(&brain_path, &to_save)?;
+        }
+        
+
+        println!("(it : {}) best score : {} {:?}, best energy : {}", i, best_brains[0].score, best_brains[0].scores, best_brains[0].energy);
+        if let Some(speciation) = &speciation {
+            println!("{}", speciation.get_summary());
         }
 
-        // wait for the threads to finish
-        for handle in handles {
-            let result = handle.join().unwrap();
-            scores.push(result);
+        // ------------------ create the next generation ------------------
+        brains = match &species_parents {
+            Some(species_parents) => generate_next_generation_by_species(params, species_parents, &best_brains, &mut rng),
+            None => generate_next_generation_from_scoring(params, &best_brains, &mut rng),
+        };
+
+        // -------------------- save the statistics of the generation -------------------------
+        let nb_species = speciation.as_ref().map_or(0, |speciation| speciation.species.len());
+        GenerationStats::new(i, &land_seeds, &scores, nb_species, generation_start.elapsed().as_secs_f64()).append(folder_path)?;
+
+        // get the seeds
+        land_seeds = generate_seeds(params, i + 1, &land_seeds, &mut rng);
+        
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+    use crate::params::FitnessAggregation;
+    use crate::selection::SelectionType;
+    use crate::utils::get_test_path;
+
+    use super::*;
+
+    /// play the brain alone on the land (the reference of the races)
+    fn brain_run(params : &GameParameters, training : &TrainingConfig, brain : Brain, seed : &str) -> u64 {
+        let mut game = Game::new(params, seed, Some(brain), None);
+        while !game.has_lost && (game.score < training.limit_score) {
+            game.step();
         }
 
-        // ----------------- get the best brains -----------------
-        // sort the scores
-        let mut best_brains_score : Vec<&(Brain, u64)> = vec![&scores[0]];
-        for score in &scores {
-            if score.1 > best_brains_score.get(0).unwrap().1 {
-                best_brains_score = vec![score];
-            }else if score.1 == best_brains_score.get(0).unwrap().1 {
-                best_brains_score.push(score);
+        game.score
+    }
+
+    #[test]
+    fn test_evaluate_generation_is_sequential() {
+        // the pool must give the same scores, in the same order, as a sequential run
+        let mut params = GameParameters::new_default();
+        params.training_nb_brain = 16;
+        let training = TrainingConfig::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brains : Vec<Brain> = (0..params.training_nb_brain).map(|_| Brain::new(&params, &mut rng)).collect();
+
+        let sequential : Vec<u64> = brains
+            .iter()
+            .map(|brain| brain_run(&params, &training, brain.clone(), "test"))
+            .collect();
+
+        let pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+        let parallel = evaluate_generation(&pool, &params, &training, brains.clone(), &["test".to_string()]);
+
+        assert_eq!(parallel.iter().map(|score| score.brain.clone()).collect::<Vec<Brain>>(), brains);
+        assert_eq!(parallel.iter().map(|score| score.scores[0]).collect::<Vec<u64>>(), sequential);
+    }
+
+    #[test]
+    fn test_crossover() {
+        let mut params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let parent_a = Brain::new(&params, &mut rng);
+        let parent_b = Brain::new(&params, &mut rng);
+
+        // web crossover : every web comes from one of the parents
+        for _ in 0..50 {
+            let child = parent_a.crossover(&parent_b, &params, &mut rng);
+            for web in &child.neurone_web {
+                assert!(parent_a.neurone_web.contains(web) || parent_b.neurone_web.contains(web));
             }
-                
         }
 
-        // sort on the energie if the score is max
-        let mut best_brains: Vec<&(Brain, u64)>;
-        if best_brains_score[0].1 == params::LIMIT_SCORE {
-            best_brains = vec![best_brains_score[0]];
-            for score in &best_brains_score {
-                if score.0.get_energie(&params) < best_brains.get(0).unwrap().0.get_energie(&params) {
-                    best_brains = vec![score];
-                }else if score.0.get_energie(&params) == best_brains.get(0).unwrap().0.get_energie(&params) {
-                    best_brains.push(score);
+        // neurone crossover : every neurone comes from one of the parents
+        params.crossover_type = CrossoverType::Neurone;
+        for _ in 0..50 {
+            let child = parent_a.crossover(&parent_b, &params, &mut rng);
+            assert!(child.neurone_web.len() <= parent_a.neurone_web.len().max(parent_b.neurone_web.len()));
+            for web in &child.neurone_web {
+                for neurone in &web.neurones {
+                    assert!(
+                        parent_a.neurone_web.iter().chain(parent_b.neurone_web.iter())
+                            .any(|parent_web| parent_web.neurones.contains(neurone))
+                    );
                 }
             }
-        }else{
-            best_brains = best_brains_score;
         }
+    }
 
-
-        // -------------------- save the progression (brain and random) -------------------------
-        if i % params::INTERVAL_TO_SAVE_RESULT == 0 || i == i_begin + params::TRAINING_NB_GENERATION - 1{
-            let brains_to_save: Vec<Brain> = best_brains.iter().map(|&(brain, _)| brain.clone()).collect();
-            let to_save = IntermediateResult {
-                brains : brains_to_save,
-                rng : rng.clone(),
-                score : best_brains[0].1,
-            };
-            // save the best brains
-            let brain_str = serde_json::to_string(&to_save).unwrap();
-            let brain_path = format!("{}brain{}.json", folder_path.clone(), i);
-            fs::write(brain_path, brain_str).expect("Unable to write file");
+    #[test]
+    fn test_mutate_all_without_crossover_is_unchanged() {
+        // a crossover rate of 0 must not change the random sequence of the training
+        let mut params = GameParameters::new_default();
+        params.training_nb_brain = 20;
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brains : Vec<Brain> = (0..3).map(|_| Brain::new(&params, &mut rng)).collect();
+
+        let mut rng_a = rng.clone();
+        let mut rng_b = rng.clone();
+        let next = Brain::mutate_all(&params, &brains, params.training_nb_brain, &mut rng_a);
+        let mut i = rng_b.gen_range(0..brains.len());
+        for brain in next {
+            assert_eq!(brain, brains[i].mutate(&params, &mut rng_b));
+            i = (i + 1) % brains.len();
         }
-        
 
-        println!("(it : {}) best score : {}, best energy : {}", i, best_brains.get(0).unwrap().1, best_brains.get(0).unwrap().0.get_energie(&params));
+        params.crossover_rate = 1.0;
+        let next = Brain::mutate_all(&params, &brains, params.training_nb_brain, &mut rng_a);
+        assert_eq!(next.len(), params.training_nb_brain as usize);
+    }
 
-        // ------------------ create the next generation ------------------
-        brains = generate_next_generation_from_scoring(&params, &best_brains, &mut rng);
-        // get the seed
-        land_seed = generate_seed(&params, i + 1, land_seed.as_str(), &mut rng);
-        
+    #[test]
+    fn test_load_last_result_skip_corrupted() {
+        let params = GameParameters::new_default();
+        let folder_path = get_test_path("checkpoint/");
+        let _ = fs::remove_dir_all(&folder_path);
+        fs::create_dir(&folder_path).unwrap();
+
+        // no brain file
+        assert!(matches!(load_last_result::<Brain>(&folder_path), Err(Error::NoCheckpoint { .. })));
+
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let result = IntermediateResult {
+            brains : vec![Brain::new(&params, &mut rng)],
+            rng : rng.clone(),
+            score : 3.0,
+            seeds : vec![params.land_seed.clone()],
+            scores : vec![vec![3]],
+            parents : Vec::new(),
+            speciation : None,
+            species_parents : Vec::new(),
+        };
+        write_json(&format!("{}brain2.json", folder_path), &result).unwrap();
+        // the last brain file is corrupted
+        fs::write(format!("{}brain10.json", folder_path), "{\"brains\" : [").unwrap();
+
+        let (i, loaded) = load_last_result(&folder_path).unwrap();
+        assert_eq!(i, 2);
+        assert_eq!(loaded, result);
+
+        // the saved brains get their saved scores back (to be speciated again on resume)
+        let brain_scores = loaded.get_brain_scores(&params);
+        assert_eq!(brain_scores.len(), 1);
+        assert_eq!(brain_scores[0].brain, result.brains[0]);
+        assert_eq!(brain_scores[0].scores, vec![3]);
+        assert_eq!(brain_scores[0].score, 3.0);
+        let old_result = IntermediateResult { scores : Vec::new(), ..result };
+        assert_eq!(old_result.get_brain_scores(&params)[0].score, 0.0);
+
+        fs::remove_dir_all(&folder_path).unwrap();
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_resume_is_exact() {
+        // a resumed training must give the same brains as an uninterrupted one (with and without the species)
+        for speciation_threshold in [None, Some(0.5)] {
+            let mut params = GameParameters::new_default();
+            params.training_nb_brain = 12;
+            params.selection = SelectionType::Tournament { size : 3, nb_selected : 6 };
+            params.terrain_seed_generation_interval = Some(3);
+            params.speciation_threshold = speciation_threshold;
+            let mut training = TrainingConfig::new_default();
+            training.nb_generation = 4;
+            training.limit_score = 100;
+            training.interval_to_save_result = 1;
+
+            let folder_a = get_test_path("uninterrupted/");
+            let folder_b = get_test_path("resumed/");
+            for folder_path in [&folder_a, &folder_b] {
+                let _ = fs::remove_dir_all(folder_path);
+                fs::create_dir(folder_path).unwrap();
+            }
+            train::<Brain>(&folder_a, &params, &training, false, Some(2)).unwrap();
+            training.nb_generation = 2;
+            train::<Brain>(&folder_b, &params, &training, false, Some(2)).unwrap();
+            train::<Brain>(&folder_b, &params, &training, true, Some(2)).unwrap();
+
+            let uninterrupted = IntermediateResult::<Brain>::new_from_file(&format!("{}brain3.json", folder_a)).unwrap();
+            let resumed = IntermediateResult::<Brain>::new_from_file(&format!("{}brain3.json", folder_b)).unwrap();
+            assert_eq!(resumed, uninterrupted);
+            match &uninterrupted.speciation {
+                Some(speciation) => assert_eq!(uninterrupted.species_parents.len(), speciation.species.len()),
+                // the parents selected several times are saved with their multiplicity
+                None => assert_eq!(uninterrupted.get_parents().len(), 6),
+            }
+            assert_eq!(uninterrupted.speciation.is_some(), speciation_threshold.is_some());
+
+            for folder_path in [&folder_a, &folder_b] {
+                fs::remove_dir_all(folder_path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_generation_multi_seed() {
+        // every brain play every terrain, the fitness is the aggregation of the scores
+        let mut params = GameParameters::new_default();
+        params.training_nb_terrain = 3;
+        params.fitness_aggregation = FitnessAggregation::Min;
+        let training = TrainingConfig::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brains : Vec<Brain> = (0..4).map(|_| Brain::new(&params, &mut rng)).collect();
+        let seeds = generate_seeds(&params, 0, &[], &mut rng);
+        assert_eq!(seeds, vec!["gra".to_string(), "gra1".to_string(), "gra2".to_string()]);
+
+        let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        for score in evaluate_generation(&pool, &params, &training, brains, &seeds) {
+            assert_eq!(score.scores.len(), 3);
+            for (seed, seed_score) in seeds.iter().zip(&score.scores) {
+                assert_eq!(brain_run(&params, &training, score.brain.clone(), seed), *seed_score);
+            }
+            assert_eq!(score.score, *score.scores.iter().min().unwrap() as f64);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use rand::Rng;
+use rand_pcg::Pcg64;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::entity::{Dinosaur, Obstacle};
+use crate::neurone::NeuroneWebAction;
+use crate::params::{GameParameters, ParentSelection};
+
+/// what plays the dinosaur and is trained by the pipeline (the neurone webs of a Brain, or a NeuralNetwork)
+/// NOTE : the training, the races and the evaluation are the same for every controller
+pub trait Controller : Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned {
+    /// create a random controller (the first generation of a training)
+    fn new_random(params : &GameParameters, rng : &mut Pcg64) -> Self;
+
+    /// mutate the controller and return a new controller
+    fn mutate(&self, params : &GameParameters, rng : &mut Pcg64) -> Self;
+
+    /// recombine two controllers and return a new controller
+    fn crossover(&self, other : &Self, params : &GameParameters, rng : &mut Pcg64) -> Self;
+
+    /// get the actions to do in this state of the game
+    /// NOTE : the speed of the game goes from 0 to 1 (see Game::get_speed)
+    fn get_activations(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> HashSet<NeuroneWebAction>;
+
+    /// get the energie of the controller (the lowest wins the ties)
+    fn get_energie(&self, params : &GameParameters) -> f64;
+
+    /// get the size of the controller for the statistics (number of neurone webs or layers, number of neurones)
+    fn get_size(&self) -> (usize, usize);
+
+    /// get the compatibility distance with another controller (0 for the same, see the speciation of the params)
+    fn get_distance(&self, other : &Self, params : &GameParameters) -> f64;
+
+    /// mutate a vect of controller into nb_brain controllers
+    /// genere the next generation (mutate all the best controllers, begin randomly)
+    /// keep all the best controllers and doesn't discard them
+    /// take a random controller from the best controllers and rotate the index
+    /// NOTE : with the crossover rate, the controller is first recombined with a second parent (see ParentSelection)
+    fn mutate_all(params : &GameParameters, brains : &[Self], nb_brain : u64, rng : &mut Pcg64) -> Vec<Self> {
+        let mut i = rng.gen_range(0..brains.len());
+        let mut next_brains = Vec::new();
+        for _ in 0..nb_brain {
+            // crossover the brain (the rate is checked first to keep the random sequence without crossover)
+            let brain = if params.crossover_rate > 0.0 && rng.gen_bool(params.crossover_rate) {
+                let j = match params.crossover_parent_selection {
+                    ParentSelection::Random => rng.gen_range(0..brains.len()),
+                    ParentSelection::Rotate => (i + 1) % brains.len(),
+                };
+                brains[i].crossover(&brains[j], params, rng)
+            }else{
+                brains[i].clone()
+            };
+            // mutate the brain
+            let brain = brain.mutate(params, rng);
+            next_brains.push(brain);
+            // rotate the best scores index
+            i += 1;
+            if i >= brains.len() {
+                i = 0;
+            }
+        }
+
+        next_brains
+    }
+}
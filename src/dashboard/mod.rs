@@ -0,0 +1,459 @@
+use std::time::Duration;
+
+use iced::widget::canvas::{Cursor, Geometry, Path, Stroke, Frame, Text};
+use iced::widget::{canvas, Canvas, Column, Row, button, slider, text};
+use iced::theme::Theme;
+use iced::{Application, executor, Command, Rectangle, Color, Point, Subscription, Length, Element};
+
+use crate::brain::{Brain, IntermediateResult};
+use crate::controller::Controller;
+use crate::game::{self, Game, CustomFlags, BrainSelection};
+use crate::network::NeuralNetwork;
+use crate::params::{GameParameters, ControllerType};
+use crate::race::{self, RaceView};
+use crate::stats::GenerationStats;
+use crate::utils::get_brain_indexes;
+
+/// the interval to reload the statistics of the training folder (a training can still be running)
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+const CHART_HEIGHT: u16 = 250;
+
+/// the data of the training folder shown by the dashboard
+#[derive(Debug, Clone, Default)]
+pub struct TrainingData {
+    /// the statistics of every generation (see GenerationStats)
+    pub stats : Vec<GenerationStats>,
+    /// the generations with a brain file (whose champion can be watched), sorted
+    pub saved_generations : Vec<u64>,
+    /// the error of the last load (the data which can be read is still shown)
+    pub error : Option<String>,
+}
+
+/// load the statistics and the list of the brain files of the training folder
+/// NOTE : the brain files are only read without statistics file (an old training folder)
+pub fn load_training_data(folder_path : &str, params : &GameParameters) -> TrainingData {
+    let mut data = TrainingData::default();
+    match get_brain_indexes(folder_path) {
+        Ok(saved_generations) => data.saved_generations = saved_generations,
+        Err(error) => data.error = Some(error.to_string()),
+    }
+    match GenerationStats::load(folder_path) {
+        Ok(stats) if !stats.is_empty() => data.stats = stats,
+        Ok(_) => {
+            let (stats, error) = match params.controller {
+                ControllerType::NeuroneWeb => load_brain_file_stats::<Brain>(folder_path, params, &data.saved_generations),
+                ControllerType::NeuralNetwork => load_brain_file_stats::<NeuralNetwork>(folder_path, params, &data.saved_generations),
+            };
+            data.stats = stats;
+            data.error = data.error.or(error);
+        },
+        Err(error) => data.error = Some(error.to_string()),
+    }
+
+    data
+}
+
+/// get the statistics of the saved generations from their brain files (with the error of the last invalid one)
+/// NOTE : only the saved brains are in the statistics, and the best score is the saved one
+fn load_brain_file_stats<C : Controller>(folder_path : &str, params : &GameParameters, saved_generations : &[u64]) -> (Vec<GenerationStats>, Option<String>) {
+    let mut stats = Vec::new();
+    let mut error = None;
+    for generation in saved_generations {
+        match IntermediateResult::<C>::new_from_file(&format!("{}brain{}.json", folder_path, generation)) {
+            Ok(result) => {
+                let mut generation_stats = GenerationStats::new(*generation, &result.seeds, &result.get_brain_scores(params), 0, 0.0);
+                generation_stats.best_score = result.score;
+                stats.push(generation_stats);
+            },
+            Err(load_error) => error = Some(load_error.to_string()),
+        }
+    }
+
+    (stats, error)
+}
+
+// ----------------- front -----------------
+#[derive(Debug, Clone)]
+pub enum Message {
+    Reload,
+    /// the data loaded in the background
+    Loaded(TrainingData),
+    Select(u32),
+    Watch,
+    Back,
+    Game(game::Message),
+    Race(race::Message),
+}
+
+/// the watched champion : a game for the neurone webs, a race of one network for the neural networks
+enum Watched {
+    Game(Box<Game>),
+    Race(Box<RaceView<NeuralNetwork>>),
+}
+
+/// a dashboard on a training folder (curves per generation, and play of the champions)
+pub struct Dashboard {
+    folder_path : String,
+    params : GameParameters,
+    data : TrainingData,
+    /// a reload is running in the background
+    is_loading : bool,
+    /// the index of the selected saved generation
+    selected : usize,
+    /// the error of the last watched brain file
+    error : Option<String>,
+    /// the watched champion
+    watched : Option<Watched>,
+}
+
+impl Dashboard {
+    /// load the data of the training folder in the background (off the ui thread)
+    fn reload(&mut self) -> Command<Message> {
+        if self.is_loading {
+            return Command::none();
+        }
+        self.is_loading = true;
+        let folder_path = self.folder_path.clone();
+        let params = self.params.clone();
+        Command::perform(async move { load_training_data(&folder_path, &params) }, Message::Loaded)
+    }
+
+    fn get_selected(&self) -> Option<u64> {
+        self.data.saved_generations.get(self.selected).copied()
+    }
+
+    /// get the points (generation, value) of a statistic
+    fn get_series(&self, value : impl Fn(&GenerationStats) -> f64) -> Vec<(f64, f64)> {
+        self.data.stats.iter().map(|stats| (stats.generation as f64, value(stats))).collect()
+    }
+
+    /// load the champion of the saved generation and play it (with the controller of the params)
+    fn watch(&mut self, generation : u64) {
+        let brain_path = format!("{}brain{}.json", self.folder_path, generation);
+        let watched = match self.params.controller {
+            ControllerType::NeuroneWeb => IntermediateResult::<Brain>::new_from_file(&brain_path).map(|result| {
+                let selection = BrainSelection::new_single(result.brains[0].clone());
+                Watched::Game(Box::new(<Game as Application>::new(CustomFlags::Brain(selection, self.params.clone(), None)).0))
+            }),
+            ControllerType::NeuralNetwork => IntermediateResult::<NeuralNetwork>::new_from_file(&brain_path).map(|result| {
+                Watched::Race(Box::new(<RaceView<NeuralNetwork> as Application>::new((self.params.clone(), vec![result.brains[0].clone()], vec!["champion".to_string()])).0))
+            }),
+        };
+        match watched {
+            Ok(watched) => {
+                self.watched = Some(watched);
+                self.error = None;
+            },
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+
+    fn view_charts(&self) -> Element<'_, Message> {
+        let selected_generation = self.get_selected();
+        let score_chart = LineChart {
+            title : "Score and energy of the champion".to_string(),
+            series : vec![
+                ("score".to_string(), Color::from_rgb8(0, 150, 0), self.get_series(|stats| stats.best_score)),
+                ("mean score".to_string(), Color::from_rgb8(200, 150, 0), self.get_series(|stats| stats.mean_score)),
+                ("energy".to_string(), Color::from_rgb8(200, 0, 0), self.get_series(|stats| stats.best_energy)),
+            ],
+            selected_x : selected_generation.map(|generation| generation as f64),
+        };
+        let complexity_chart = LineChart {
+            title : "Population and complexity".to_string(),
+            series : vec![
+                ("population size".to_string(), Color::from_rgb8(0, 150, 150), self.get_series(|stats| stats.population_size as f64)),
+                ("species".to_string(), Color::from_rgb8(0, 0, 200), self.get_series(|stats| stats.nb_species as f64)),
+                ("neurone webs".to_string(), Color::from_rgb8(200, 0, 200), self.get_series(|stats| stats.mean_nb_neurone_web)),
+                ("neurones".to_string(), Color::BLACK, self.get_series(|stats| stats.mean_nb_neurone)),
+            ],
+            selected_x : selected_generation.map(|generation| generation as f64),
+        };
+
+        let mut controls = Row::new().spacing(20).padding(10);
+        match selected_generation {
+            Some(generation) => {
+                let description = match self.data.stats.iter().find(|stats| stats.generation == generation) {
+                    Some(stats) => format!("generation {} : score {}, energy {:.0}", generation, stats.best_score, stats.best_energy),
+                    None => format!("generation {}", generation),
+                };
+                controls = controls
+                    .push(text(description))
+                    .push(
+                        slider(0..=(self.data.saved_generations.len() as u32 - 1), self.selected as u32, Message::Select)
+                            .width(Length::Fixed(400.0))
+                    )
+                    .push(button("Watch the champion").on_press(Message::Watch));
+            },
+            None => {
+                controls = controls.push(text(format!("No brain file in {}", self.folder_path)));
+            },
+        }
+        for error in [&self.data.error, &self.error].into_iter().flatten() {
+            controls = controls.push(text(error));
+        }
+
+        Column::new()
+            .push(Canvas::new(score_chart).width(Length::Fill).height(CHART_HEIGHT))
+            .push(Canvas::new(complexity_chart).width(Length::Fill).height(CHART_HEIGHT))
+            .push(controls)
+            .into()
+    }
+}
+
+impl Application for Dashboard {
+    type Theme = Theme;
+    type Executor = executor::Default;
+    type Message = Message;
+    /// the training folder (with the slash at the end) and its params
+    type Flags = (String, GameParameters);
+
+    fn new((folder_path, params): Self::Flags) -> (Self, iced::Command<Self::Message>) {
+        let mut dashboard = Self {
+            folder_path,
+            params,
+            data : TrainingData::default(),
+            is_loading : false,
+            selected : 0,
+            error : None,
+            watched : None,
+        };
+        let command = dashboard.reload();
+        (dashboard, command)
+    }
+
+    fn title(&self) -> String {
+        format!("Chrome Dinosaur - training {}", self.folder_path)
+    }
+
+    fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
+        match message {
+            Message::Reload => self.reload(),
+            Message::Loaded(data) => {
+                // keep the last generation selected if it was
+                let was_last = self.selected + 1 >= self.data.saved_generations.len();
+                self.data = data;
+                self.is_loading = false;
+                if was_last || self.selected >= self.data.saved_generations.len() {
+                    self.selected = self.data.saved_generations.len().saturating_sub(1);
+                }
+                Command::none()
+            },
+            Message::Select(selected) => {
+                self.selected = selected as usize;
+                Command::none()
+            },
+            Message::Watch => {
+                if let Some(generation) = self.get_selected() {
+                    self.watch(generation);
+                }
+                Command::none()
+            },
+            Message::Back => {
+                self.watched = None;
+                Command::none()
+            },
+            Message::Game(message) => {
+                match &mut self.watched {
+                    Some(Watched::Game(game)) => Application::update(game.as_mut(), message).map(Message::Game),
+                    _ => Command::none(),
+                }
+            },
+            Message::Race(message) => {
+                match &mut self.watched {
+                    Some(Watched::Race(race)) => Application::update(race.as_mut(), message).map(Message::Race),
+                    _ => Command::none(),
+                }
+            },
+        }
+    }
+
+    fn view(&self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
+        let watched_view = match &self.watched {
+            Some(Watched::Game(game)) => Application::view(game.as_ref()).map(Message::Game),
+            Some(Watched::Race(race)) => Application::view(race.as_ref()).map(Message::Race),
+            None => return self.view_charts(),
+        };
+        Column::new()
+            .push(button("Back to the dashboard").on_press(Message::Back))
+            .push(watched_view)
+            .into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        match &self.watched {
+            Some(Watched::Game(game)) => Application::subscription(game.as_ref()).map(Message::Game),
+            Some(Watched::Race(race)) => Application::subscription(race.as_ref()).map(Message::Race),
+            None => iced::time::every(RELOAD_INTERVAL).map(|_| Message::Reload),
+        }
+    }
+}
+
+/// a series of a chart : (name, color, points)
+type Series = (String, Color, Vec<(f64, f64)>);
+
+/// a chart of several series, each one is scaled on its own maximum
+struct LineChart {
+    title : String,
+    series : Vec<Series>,
+    /// the x to highlight
+    selected_x : Option<f64>,
+}
+
+impl canvas::Program<Message> for LineChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        let margin = 40.0;
+        let width = bounds.width - 2.0 * margin;
+        let height = bounds.height - 2.0 * margin;
+
+        frame.fill_text(Text {
+            content: self.title.clone(),
+            position: Point { x: margin, y: 5.0 },
+            size: 20.0,
+            color: Color::BLACK,
+            ..Text::default()
+        });
+
+        // axes
+        let axes = Path::new(|builder| {
+            builder.move_to(Point { x: margin, y: margin });
+            builder.line_to(Point { x: margin, y: margin + height });
+            builder.line_to(Point { x: margin + width, y: margin + height });
+        });
+        frame.stroke(&axes, Stroke::default().with_color(Color::BLACK));
+
+        // the x range is shared by all the series
+        let all_x = self.series.iter().flat_map(|(_, _, points)| points.iter().map(|(x, _)| *x));
+        let min_x = all_x.clone().fold(f64::INFINITY, f64::min);
+        let max_x = all_x.fold(f64::NEG_INFINITY, f64::max);
+        if !min_x.is_finite() {
+            return vec![frame.into_geometry()];
+        }
+        let range_x = (max_x - min_x).max(1.0);
+        let to_x = |x : f64| margin + ((x - min_x) / range_x) as f32 * width;
+
+        // the selected x
+        if let Some(selected_x) = self.selected_x {
+            let line = Path::line(
+                Point { x: to_x(selected_x), y: margin },
+                Point { x: to_x(selected_x), y: margin + height },
+            );
+            frame.stroke(&line, Stroke::default().with_color(Color::from_rgb8(150, 150, 150)));
+        }
+        frame.fill_text(Text {
+            content: format!("{}", min_x),
+            position: Point { x: margin, y: margin + height + 5.0 },
+            color: Color::BLACK,
+            ..Text::default()
+        });
+        frame.fill_text(Text {
+            content: format!("{}", max_x),
+            position: Point { x: margin + width - 40.0, y: margin + height + 5.0 },
+            color: Color::BLACK,
+            ..Text::default()
+        });
+
+        // the series
+        let mut legend_x = margin + 400.0;
+        for (name, color, points) in &self.series {
+            let max_y = points.iter().map(|(_, y)| *y).fold(0.0, f64::max).max(f64::EPSILON);
+            let to_y = |y : f64| margin + height - (y / max_y) as f32 * height;
+            let line = Path::new(|builder| {
+                for (i, (x, y)) in points.iter().enumerate() {
+                    let point = Point { x: to_x(*x), y: to_y(*y) };
+                    if i == 0 {
+                        builder.move_to(point);
+                    }else{
+                        builder.line_to(point);
+                    }
+                }
+            });
+            frame.stroke(&line, Stroke::default().with_color(*color).with_width(2.0));
+
+            frame.fill_text(Text {
+                content: format!("{} (max {:.0})", name, max_y),
+                position: Point { x: legend_x, y: 5.0 },
+                size: 16.0,
+                color: *color,
+                ..Text::default()
+            });
+            legend_x += 200.0;
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::brain::BrainScore;
+    use crate::stats::STATS_FILE_NAME;
+    use crate::utils::{get_test_path, str_to_u8_array, write_json};
+
+    use super::*;
+
+    #[test]
+    fn test_load_training_data() {
+        let params = GameParameters::new_default();
+        let folder_path = get_test_path("dashboard/");
+        let _ = fs::remove_dir_all(&folder_path);
+        fs::create_dir(&folder_path).unwrap();
+        assert_eq!(load_training_data(&folder_path, &params).stats, Vec::new());
+
+        let scores = BrainScore::new_random_tests(&params, &[4.0, 10.0]);
+        for generation in 0..3 {
+            GenerationStats::new(generation, &["gra".to_string()], &scores, 0, 1.0).append(&folder_path).unwrap();
+        }
+        // only the name of the brain files is read
+        fs::write(format!("{}brain2.json", folder_path), "{\"brains\" : [").unwrap();
+
+        let data = load_training_data(&folder_path, &params);
+        assert_eq!(data.stats.iter().map(|stats| stats.generation).collect::<Vec<u64>>(), vec![0, 1, 2]);
+        assert_eq!(data.stats[2].best_score, 10.0);
+        assert_eq!(data.saved_generations, vec![2]);
+        assert!(data.error.is_none());
+
+        // without statistics file, the scores of the brain files are used (and the invalid one is reported)
+        fs::remove_file(format!("{}{}", folder_path, STATS_FILE_NAME)).unwrap();
+        let result = IntermediateResult {
+            brains : scores.iter().map(|score| score.brain.clone()).collect(),
+            rng : Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str())),
+            score : 10.0,
+            seeds : vec!["gra".to_string()],
+            scores : vec![vec![10], vec![4]],
+            parents : Vec::new(),
+            speciation : None,
+            species_parents : Vec::new(),
+        };
+        write_json(&format!("{}brain1.json", folder_path), &result).unwrap();
+        let data = load_training_data(&folder_path, &params);
+        assert_eq!(data.saved_generations, vec![1, 2]);
+        assert_eq!(data.stats.iter().map(|stats| stats.generation).collect::<Vec<u64>>(), vec![1]);
+        assert_eq!(data.stats[0].best_score, 10.0);
+        assert_eq!(data.stats[0].mean_score, 7.0);
+        assert_eq!(data.stats[0].population_size, 2);
+        assert!(data.error.unwrap().contains("brain2.json"));
+
+        // the statistics file can't be read
+        fs::create_dir(format!("{}{}", folder_path, STATS_FILE_NAME)).unwrap();
+        let data = load_training_data(&folder_path, &params);
+        assert!(data.stats.is_empty());
+        assert!(data.error.unwrap().contains(STATS_FILE_NAME));
+
+        fs::remove_dir_all(&folder_path).unwrap();
+    }
+}
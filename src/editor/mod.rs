@@ -0,0 +1,515 @@
+use iced::widget::canvas::{Cursor, Geometry, Cache, Path, Stroke};
+use iced::widget::{canvas, Canvas, Column, Row, button, text};
+use iced::theme::Theme;
+use iced::{Application, executor, Command, Rectangle, Size, Color, Point, Subscription, keyboard, mouse};
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+use crate::brain::{Brain, IntermediateResult};
+use crate::entity::Dinosaur;
+use crate::error::{Error, Result};
+use crate::game::{self, Game, CustomFlags, BrainSelection, draw_neurone_web, get_opposite};
+use crate::network::NeuralNetwork;
+use crate::neurone::{Neurone, NeuroneWeb, NeuroneWebAction, NeuroneActivationCondition, NeuroneActivation, NeuroneAnchor};
+use crate::params::GameParameters;
+use crate::utils::{str_to_u8_array, write_json};
+
+/// the height of the controls under the game
+const CONTROLS_HEIGHT: u32 = 100;
+
+// ----------------- front -----------------
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// left click on the canvas (screen coordinates)
+    Press(Point),
+    /// right click on the canvas (screen coordinates)
+    RightPress(Point),
+    /// the cursor moved while dragging a neurone (screen coordinates)
+    Drag(Point),
+    Release,
+    ToggleCondition,
+    ToggleActivation,
+    /// switch the neurone between the screen and the dinosaur anchor
+    ToggleAnchor,
+    DeleteNeurone,
+    NewWeb,
+    DeleteWeb,
+    /// select the next (or previous) web
+    SelectWeb(i64),
+    SetAction(NeuroneWebAction),
+    TestPlay,
+    Back,
+    Save,
+    Game(game::Message),
+}
+
+/// an editor of brain : add, move, delete and change the neurones and the webs by hand
+pub struct Editor {
+    params : GameParameters,
+    brain : Brain,
+    /// the brain file of the edited brain, only this brain is replaced on save (the other brains, the rng and the scores are kept)
+    result : IntermediateResult,
+    /// the index of the edited brain in the brain file
+    brain_index : usize,
+    /// the brain file where the brain is saved
+    save_path : String,
+
+    selected_web : Option<usize>,
+    /// the selected neurone (index of the web, index of the neurone)
+    selected_neurone : Option<(usize, usize)>,
+    dragging : bool,
+    /// the result of the last save
+    status : String,
+
+    /// the game to test the brain
+    game : Option<Game>,
+    cache : Cache,
+}
+
+impl Editor {
+    /// edit the brain at the index of the brain file
+    /// NOTE : the index must be in the brain file
+    pub fn new_editor(params : GameParameters, result : IntermediateResult, brain_index : usize, save_path : String) -> Self {
+        let brain = result.brains[brain_index].clone();
+        Self {
+            selected_web : if brain.neurone_web.is_empty() { None } else { Some(0) },
+            params,
+            brain,
+            result,
+            brain_index,
+            save_path,
+            selected_neurone : None,
+            dragging : false,
+            status : String::new(),
+            game : None,
+            cache : Cache::default(),
+        }
+    }
+
+    /// convert a point of the screen to the game coordinates (the y goes up)
+    fn to_game_point(&self, point : Point) -> (f64, f64) {
+        (point.x as f64, self.params.game_height as f64 - point.y as f64)
+    }
+
+    /// get the neurone under the point (game coordinates), the last drawn first
+    fn find_neurone(&self, (x, y) : (f64, f64)) -> Option<(usize, usize)> {
+        for (i, neurone_web) in self.brain.neurone_web.iter().enumerate().rev() {
+            for (j, neurone) in neurone_web.neurones.iter().enumerate().rev() {
+                if x >= neurone.x && x <= neurone.x + neurone.width as f64 && y >= neurone.y && y <= neurone.y + neurone.height as f64 {
+                    return Some((i, j));
+                }
+            }
+        }
+        None
+    }
+
+    /// move the neurone centered on the point (game coordinates), inside the same limits as the mutation
+    fn place_neurone(params : &GameParameters, neurone : &mut Neurone, (x, y) : (f64, f64)) {
+        neurone.x = (x - neurone.width as f64 / 2.0).clamp(0.0, (params.game_width - params.neurone_width) as f64);
+        neurone.y = (y - neurone.height as f64 / 2.0).clamp(neurone.activation_condition.get_min_y(params), (params.game_height - params.neurone_height) as f64);
+    }
+
+    fn get_selected_neurone(&mut self) -> Option<&mut Neurone> {
+        let (i, j) = self.selected_neurone?;
+        self.brain.neurone_web.get_mut(i)?.neurones.get_mut(j)
+    }
+
+    /// select the neurone under the point, or add a neurone to the selected web (a new web if there is none)
+    fn press(&mut self, point : (f64, f64)) {
+        if let Some((i, j)) = self.find_neurone(point) {
+            self.selected_web = Some(i);
+            self.selected_neurone = Some((i, j));
+            self.dragging = true;
+            return;
+        }
+
+        let i = match self.selected_web {
+            Some(i) => i,
+            None => {
+                self.new_web();
+                self.brain.neurone_web.len() - 1
+            },
+        };
+        let mut neurone = Neurone::new(&self.params, 0.0, 0.0, NeuroneActivationCondition::Obstacle, NeuroneActivation::Activate);
+        Self::place_neurone(&self.params, &mut neurone, point);
+        self.brain.neurone_web[i].neurones.push(neurone);
+        self.selected_neurone = Some((i, self.brain.neurone_web[i].neurones.len() - 1));
+    }
+
+    fn drag(&mut self, point : (f64, f64)) {
+        let params = self.params.clone();
+        if let Some(neurone) = self.get_selected_neurone() {
+            Self::place_neurone(&params, neurone, point);
+        }
+    }
+
+    fn delete_neurone(&mut self, (i, j) : (usize, usize)) {
+        self.brain.neurone_web[i].neurones.remove(j);
+        self.selected_neurone = None;
+        self.dragging = false;
+    }
+
+    fn new_web(&mut self) {
+        self.brain.neurone_web.push(NeuroneWeb {
+            neurones : Vec::new(),
+            action : self.params.commands.first().cloned().unwrap_or(NeuroneWebAction::Jump),
+        });
+        self.selected_web = Some(self.brain.neurone_web.len() - 1);
+        self.selected_neurone = None;
+    }
+
+    fn delete_web(&mut self) {
+        if let Some(i) = self.selected_web {
+            self.brain.neurone_web.remove(i);
+            self.selected_web = if self.brain.neurone_web.is_empty() { None } else { Some(i.min(self.brain.neurone_web.len() - 1)) };
+            self.selected_neurone = None;
+        }
+    }
+
+    /// save the brain in its brain file (readable with watch)
+    pub fn save(&self) -> Result<()> {
+        let mut result = self.result.clone();
+        result.brains[self.brain_index] = self.brain.clone();
+        write_json(&self.save_path, &result)
+    }
+
+    fn view_controls(&self) -> Column<'_, Message> {
+        let web_text = match self.selected_web {
+            Some(i) => format!("web {}/{} ({})", i + 1, self.brain.neurone_web.len(), self.brain.neurone_web[i].action),
+            None => "no web".to_string(),
+        };
+        let mut actions = Row::new().spacing(10)
+            .push(button("New web").on_press(Message::NewWeb))
+            .push(button("Delete web").on_press(Message::DeleteWeb))
+            .push(button("<").on_press(Message::SelectWeb(-1)))
+            .push(button(">").on_press(Message::SelectWeb(1)));
+        for action in &self.params.commands {
+            actions = actions.push(button(text(format!("{}", action))).on_press(Message::SetAction(action.clone())));
+        }
+        actions = actions
+            .push(button("Test play").on_press(Message::TestPlay))
+            .push(button("Save").on_press(Message::Save));
+
+        Column::new()
+            .spacing(5)
+            .padding(5)
+            .push(actions)
+            .push(text(format!(
+                "{}, energy {:.0}. {}",
+                web_text, self.brain.get_energie(&self.params), self.status
+            )))
+            .push(text("click : add or select, drag : move, right click : delete, [c] condition, [a] activation, [r] move with the dinosaur"))
+    }
+}
+
+impl Application for Editor {
+    type Theme = Theme;
+    type Executor = executor::Default;
+    type Message = Message;
+    /// the params, the brain file to edit, the index of the edited brain and the path where it is saved
+    type Flags = (GameParameters, IntermediateResult, usize, String);
+
+    fn new((params, result, brain_index, save_path): Self::Flags) -> (Self, iced::Command<Self::Message>) {
+        (Self::new_editor(params, result, brain_index, save_path), Command::none())
+    }
+
+    fn title(&self) -> String {
+        format!("Chrome Dinosaur - brain editor {}", self.save_path)
+    }
+
+    fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
+        match message {
+            Message::Press(point) => self.press(self.to_game_point(point)),
+            Message::RightPress(point) => {
+                if let Some(neurone) = self.find_neurone(self.to_game_point(point)) {
+                    self.delete_neurone(neurone);
+                }
+            },
+            Message::Drag(point) => self.drag(self.to_game_point(point)),
+            Message::Release => self.dragging = false,
+            Message::ToggleCondition => {
+                // the next condition possible with the commands (the neurone goes up if it is under its new minimum)
+                let params = self.params.clone();
+                let conditions = params.get_neurone_conditions();
+                if let Some(neurone) = self.get_selected_neurone() {
+                    let i = conditions.iter().position(|condition| *condition == neurone.activation_condition).map_or(0, |i| i + 1);
+                    neurone.activation_condition = conditions[i % conditions.len()];
+                    neurone.y = neurone.y.max(neurone.activation_condition.get_min_y(&params));
+                    // a neurone on the state of the dinosaur has no position, so no anchor
+                    if neurone.activation_condition.is_proprioceptive() {
+                        neurone.anchor = NeuroneAnchor::Screen;
+                    }
+                }
+            },
+            Message::ToggleActivation => {
+                if let Some(neurone) = self.get_selected_neurone() {
+                    neurone.activation = match neurone.activation {
+                        NeuroneActivation::Activate => NeuroneActivation::PreventActivate,
+                        NeuroneActivation::PreventActivate => NeuroneActivation::Activate,
+                    };
+                }
+            },
+            Message::ToggleAnchor => {
+                if let Some(neurone) = self.get_selected_neurone().filter(|neurone| !neurone.activation_condition.is_proprioceptive()) {
+                    neurone.anchor = neurone.anchor.toggle();
+                }
+            },
+            Message::DeleteNeurone => {
+                if let Some(neurone) = self.selected_neurone {
+                    self.delete_neurone(neurone);
+                }
+            },
+            Message::NewWeb => self.new_web(),
+            Message::DeleteWeb => self.delete_web(),
+            Message::SelectWeb(delta) => {
+                let nb_web = self.brain.neurone_web.len() as i64;
+                if nb_web > 0 {
+                    let i = self.selected_web.unwrap_or(0) as i64;
+                    self.selected_web = Some((i + delta).rem_euclid(nb_web) as usize);
+                    self.selected_neurone = None;
+                }
+            },
+            Message::SetAction(action) => {
+                if let Some(i) = self.selected_web {
+                    self.brain.neurone_web[i].action = action;
+                }
+            },
+            Message::TestPlay => {
+                let (game, _) = <Game as Application>::new(CustomFlags::Brain(BrainSelection::new_single(self.brain.clone()), self.params.clone(), None));
+                self.game = Some(game);
+            },
+            Message::Back => self.game = None,
+            Message::Save => {
+                self.status = match self.save() {
+                    Ok(()) => format!("Saved in {}", self.save_path),
+                    Err(error) => format!("{}", error),
+                };
+            },
+            Message::Game(message) => {
+                if let Some(game) = &mut self.game {
+                    return Application::update(game, message).map(Message::Game);
+                }
+            },
+        }
+
+        self.cache.clear();
+        Command::none()
+    }
+
+    fn view(&self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
+        match &self.game {
+            Some(game) => Column::new()
+                .push(button("Back to the editor").on_press(Message::Back))
+                .push(Application::view(game).map(Message::Game))
+                .into(),
+            None => Column::new()
+                .push(
+                    Canvas::new(self)
+                        .width(self.params.game_width)
+                        .height(self.params.game_height)
+                )
+                .push(self.view_controls())
+                .into(),
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        match &self.game {
+            Some(game) => Application::subscription(game).map(Message::Game),
+            None => Subscription::none(),
+        }
+    }
+}
+
+impl canvas::Program<Message> for Editor {
+    type State = ();
+
+    // catch the mouse and the keyboard
+    fn update(
+            &self,
+            _state: &mut Self::State,
+            event: canvas::Event,
+            bounds: Rectangle,
+            cursor: Cursor,
+        ) -> (canvas::event::Status, Option<Message>) {
+        let position = cursor.position_in(&bounds);
+        let message = match (event, position) {
+            (canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), Some(position)) => Some(Message::Press(position)),
+            (canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)), Some(position)) => Some(Message::RightPress(position)),
+            (canvas::Event::Mouse(mouse::Event::CursorMoved { .. }), Some(position)) if self.dragging => Some(Message::Drag(position)),
+            (canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)), _) if self.dragging => Some(Message::Release),
+            (canvas::Event::Keyboard(keyboard::Event::CharacterReceived('c')), _) => Some(Message::ToggleCondition),
+            (canvas::Event::Keyboard(keyboard::Event::CharacterReceived('a')), _) => Some(Message::ToggleActivation),
+            (canvas::Event::Keyboard(keyboard::Event::CharacterReceived('r')), _) => Some(Message::ToggleAnchor),
+            (canvas::Event::Keyboard(keyboard::Event::KeyPressed { key_code : keyboard::KeyCode::Delete, .. }), _) => Some(Message::DeleteNeurone),
+            _ => None,
+        };
+
+        match message {
+            Some(message) => (canvas::event::Status::Captured, Some(message)),
+            None => (canvas::event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let geometry = self.cache.draw(bounds.size(), |frame| {
+            // the dinosaur at its start position, to place the neurones
+            let dinosaur = Dinosaur::new_dinosaur(&self.params, 0.0);
+            frame.fill_rectangle(
+                get_opposite(&self.params, Point { x: (dinosaur.x as f32), y: (dinosaur.y as f32) }, dinosaur.height as f32),
+                Size { width: (dinosaur.width as f32), height: (dinosaur.height as f32) },
+                Color::BLACK
+            );
+
+            for (i, neurone_web) in self.brain.neurone_web.iter().enumerate() {
+                draw_neurone_web(frame, &self.params, neurone_web, None, None, self.selected_web == Some(i));
+            }
+
+            // the selected neurone
+            if let Some((i, j)) = self.selected_neurone {
+                let neurone = &self.brain.neurone_web[i].neurones[j];
+                let margin = 4.0;
+                let outline = Path::rectangle(
+                    get_opposite(
+                        &self.params,
+                        Point { x: neurone.x as f32 - margin, y: neurone.y as f32 - margin },
+                        neurone.height as f32 + 2.0 * margin
+                    ),
+                    Size { width: neurone.width as f32 + 2.0 * margin, height: neurone.height as f32 + 2.0 * margin }
+                );
+                frame.stroke(&outline, Stroke::default().with_color(Color::from_rgb8(0, 120, 255)).with_width(2.0));
+            }
+        });
+
+        vec![geometry]
+    }
+}
+
+/// load the brain file to edit
+/// NOTE : a brain file of neural networks can't be edited (only the neurone webs are drawn)
+pub fn load_brain_file(path : &str) -> Result<IntermediateResult> {
+    IntermediateResult::<Brain>::new_from_file(path).map_err(|error| match error {
+        Error::CorruptCheckpoint { .. } if IntermediateResult::<NeuralNetwork>::new_from_file(path).is_ok() => {
+            Error::NotEditable { path : path.to_string() }
+        },
+        error => error,
+    })
+}
+
+/// a new brain file with an empty brain, to edit a brain from scratch
+pub fn new_brain_file(params : &GameParameters) -> IntermediateResult {
+    IntermediateResult {
+        brains : vec![Brain { neurone_web : Vec::new() }],
+        rng : Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str())),
+        score : 0.0,
+        seeds : Vec::new(),
+        scores : Vec::new(),
+        parents : Vec::new(),
+        speciation : None,
+        species_parents : Vec::new(),
+    }
+}
+
+/// get the window size of the editor
+pub fn get_window_size(params : &GameParameters) -> (u32, u32) {
+    (params.game_width as u32, params.game_height as u32 + CONTROLS_HEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::Controller;
+    use crate::neurone::NeuroneAnchor;
+    use crate::utils::get_test_path;
+
+    use super::*;
+
+    #[test]
+    fn test_edit_brain() {
+        let params = GameParameters::new_default();
+        let path = get_test_path("editor.json");
+        let mut editor = Editor::new_editor(params.clone(), new_brain_file(&params), 0, path.clone());
+
+        // a click on an empty brain create a web and a neurone centered on the click
+        editor.press((600.0, 300.0));
+        assert_eq!(editor.brain.neurone_web.len(), 1);
+        let neurone = editor.brain.neurone_web[0].neurones[0];
+        assert_eq!(neurone.x, 600.0 - params.neurone_width as f64 / 2.0);
+        assert_eq!(neurone.y, 300.0 - params.neurone_height as f64 / 2.0);
+        assert_eq!(editor.find_neurone((600.0, 300.0)), Some((0, 0)));
+
+        // a click on the neurone select it, then it can be dragged (inside the screen)
+        editor.selected_neurone = None;
+        editor.press((600.0, 300.0));
+        assert_eq!(editor.selected_neurone, Some((0, 0)));
+        assert!(editor.dragging);
+        editor.drag((-100.0, 0.0));
+        let neurone = editor.brain.neurone_web[0].neurones[0];
+        assert_eq!(neurone.x, 0.0);
+        assert_eq!(neurone.y, params.hole_height as f64 + 5.0);
+        assert_eq!(editor.find_neurone((600.0, 300.0)), None);
+
+        let _ = Application::update(&mut editor, Message::ToggleAnchor);
+        assert_eq!(editor.brain.neurone_web[0].neurones[0].anchor, NeuroneAnchor::Dinosaur);
+        // toggle the condition (the conditions on the dinosaur come after the obstacle, they have no anchor)
+        let _ = Application::update(&mut editor, Message::ToggleCondition);
+        assert_eq!(editor.brain.neurone_web[0].neurones[0].activation_condition, NeuroneActivationCondition::OnGround);
+        assert_eq!(editor.brain.neurone_web[0].neurones[0].anchor, NeuroneAnchor::Screen);
+        let _ = Application::update(&mut editor, Message::ToggleAnchor);
+        assert_eq!(editor.brain.neurone_web[0].neurones[0].anchor, NeuroneAnchor::Screen);
+
+        // a second web with a bend action
+        let _ = Application::update(&mut editor, Message::NewWeb);
+        let _ = Application::update(&mut editor, Message::SetAction(NeuroneWebAction::Bend));
+        editor.press((700.0, 300.0));
+        assert_eq!(editor.brain.neurone_web[1].neurones.len(), 1);
+        assert_eq!(editor.brain.neurone_web[1].action, NeuroneWebAction::Bend);
+
+        // the saved file is a brain file
+        editor.save().unwrap();
+        let loaded = IntermediateResult::<Brain>::new_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.brains[0], editor.brain);
+
+        // delete the neurone then the web
+        editor.delete_neurone((1, 0));
+        assert!(editor.brain.neurone_web[1].neurones.is_empty());
+        let _ = Application::update(&mut editor, Message::DeleteWeb);
+        assert_eq!(editor.brain.neurone_web.len(), 1);
+        assert_eq!(editor.selected_web, Some(0));
+    }
+
+    #[test]
+    fn test_save_keep_brain_file() {
+        let params = GameParameters::new_default();
+        let path = get_test_path("editor_keep.json");
+        let mut result = new_brain_file(&params);
+        let mut rng = Pcg64::seed_from_u64(3);
+        result.brains = (0..3).map(|_| Brain::new_random(&params, &mut rng)).collect();
+        result.score = 12.0;
+        result.seeds = vec!["abc".to_string()];
+        result.scores = vec![vec![10], vec![12], vec![14]];
+
+        // only the edited brain is replaced
+        let mut editor = Editor::new_editor(params.clone(), result.clone(), 1, path.clone());
+        assert_eq!(editor.brain, result.brains[1]);
+        editor.brain.neurone_web.clear();
+        editor.save().unwrap();
+        let loaded = IntermediateResult::<Brain>::new_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        result.brains[1].neurone_web.clear();
+        assert_eq!(loaded, result);
+
+        // a brain file of neural networks can't be edited
+        let network_result = IntermediateResult { brains : vec![NeuralNetwork::new(&params, &mut rng)], rng, score : 0.0, seeds : Vec::new(), scores : Vec::new(), parents : Vec::new(), speciation : None, species_parents : Vec::new() };
+        write_json(&path, &network_result).unwrap();
+        let error = load_brain_file(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(error, Error::NotEditable { .. }));
+    }
+}
@@ -1,11 +1,15 @@
+use std::collections::HashSet;
+use std::fmt;
 
 
-use std::{time::Instant};
 
-use crate::{params::GameParameters};
+use serde::{Serialize, Deserialize};
+
+use crate::neurone::NeuroneWebAction;
+use crate::params::GameParameters;
 
 /// The different type of obstacle
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ObstacleEntityType {
     Cactus = 0,
     Rock = 1,
@@ -14,7 +18,19 @@ pub enum ObstacleEntityType {
     Pterodactyle = 4,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+impl fmt::Display for ObstacleEntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ObstacleEntityType::Cactus => write!(f, "Cactus"),
+            ObstacleEntityType::Rock => write!(f, "Rock"),
+            ObstacleEntityType::PterodactyleWithRock => write!(f, "PterodactyleWithRock"),
+            ObstacleEntityType::Hole => write!(f, "Hole"),
+            ObstacleEntityType::Pterodactyle => write!(f, "Pterodactyle"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ObstacleGenerateType {
     Cactus = 0,
     Rock = 1,
@@ -24,7 +40,7 @@ pub enum ObstacleGenerateType {
 }
 
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Obstacle {
     /// bottom left point x
     pub x: f64,
@@ -34,14 +50,15 @@ pub struct Obstacle {
     pub height: u16,
     pub velocity: f64,// horizontal px/s
     pub type_: ObstacleEntityType,
-    pub last_time_update: Instant,
+    /// simulated time of the last update (in seconds since the start of the game)
+    pub last_time_update: f64,
 
     params : GameParameters,
 }
 
 impl Obstacle {
     /// Create a new cactus obstacle
-    fn new_cactus(params : &GameParameters, x: f64, velocity : f64, last_time_update : Instant) -> Self {
+    fn new_cactus(params : &GameParameters, x: f64, velocity : f64, last_time_update : f64) -> Self {
         Self {
             x,
             y : 0.0,
@@ -56,7 +73,7 @@ impl Obstacle {
     }
 
     /// Create a new rock obstacle
-    fn new_rock(params : &GameParameters, x: f64, velocity : f64, last_time_update : Instant) -> Self {
+    fn new_rock(params : &GameParameters, x: f64, velocity : f64, last_time_update : f64) -> Self {
         Self {
             x,
             y : 0.0,
@@ -69,33 +86,33 @@ impl Obstacle {
         }
     }
 
-    fn new_pterodactyle_with_rock(params : &GameParameters, x: f64, velocity : f64, last_time_update : Instant) -> Self {
+    fn new_pterodactyle_with_rock(params : &GameParameters, x: f64, velocity : f64, last_time_update : f64) -> Self {
         Self {
             x : x - params.pterodactyle_offset_with_rock as f64 - params.pterodactyle_width as f64,
             y : params.pterodactyle_flying_height_with_rock as f64,
             width : params.pterodactyle_width,
             height : params.pterodactyle_height,
-            velocity : velocity,
+            velocity,
             type_: ObstacleEntityType::PterodactyleWithRock,
             last_time_update,
             params : params.clone(),
         }
     }
 
-    fn new_pterodactyle(params : &GameParameters, x: f64, velocity : f64, last_time_update : Instant) -> Self {
+    fn new_pterodactyle(params : &GameParameters, x: f64, velocity : f64, last_time_update : f64) -> Self {
         Self {
             x,
             y : params.pterodactyle_flying_height_without_rock as f64,
             width : params.pterodactyle_width,
             height : params.pterodactyle_height,
-            velocity : velocity,
+            velocity,
             type_: ObstacleEntityType::Pterodactyle,
             last_time_update,
             params : params.clone(),
         }
     }
 
-    fn new_hole(params : &GameParameters, x: f64, velocity : f64, last_time_update : Instant) -> Self {
+    fn new_hole(params : &GameParameters, x: f64, velocity : f64, last_time_update : f64) -> Self {
         Self {
             x : x - params.hole_width as f64,
             y : 0.0,
@@ -109,7 +126,7 @@ impl Obstacle {
     }
 
     /// wrapper to create an obstacle
-    pub fn new(params : &GameParameters, x: f64, velocity : f64, last_time_update : Instant, type_ : ObstacleEntityType) -> Self {
+    pub fn new(params : &GameParameters, x: f64, velocity : f64, last_time_update : f64, type_ : ObstacleEntityType) -> Self {
         let x = x + 400.0;
         match type_ {
             ObstacleEntityType::Cactus => Obstacle::new_cactus(params, x, velocity, last_time_update),
@@ -121,14 +138,13 @@ impl Obstacle {
     }
 
     /// Update the obstacle position
-    pub fn update(&mut self, now: Instant) {
+    pub fn update(&mut self, now: f64) {
         if now < self.last_time_update {// if the time is in the future, we don't update
             return;
         }
 
-        let delta = now.duration_since(self.last_time_update).as_secs_f64();
-       
-        
+        let delta = now - self.last_time_update;
+
         self.x -= delta * self.velocity;
         
         self.last_time_update = now;
@@ -139,7 +155,7 @@ impl Obstacle {
 }
 
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Dinosaur {
     /// bottom left point x
     pub x: f64,
@@ -148,7 +164,8 @@ pub struct Dinosaur {
     pub width: u16,
     pub height: u16,
     pub velocity: f64,// vertical px/s
-    pub last_time_update: Instant,// in seconds
+    /// simulated time of the last update (in seconds since the start of the game)
+    pub last_time_update: f64,
 
     pub is_bending : bool,
 
@@ -157,7 +174,7 @@ pub struct Dinosaur {
 
 
 impl Dinosaur {
-    pub fn new_dinosaur(params : &GameParameters, last_time_update : Instant) -> Self {
+    pub fn new_dinosaur(params : &GameParameters, last_time_update : f64) -> Self {
         Self {
             x : params.dinausor_x,
             y : 0.0,
@@ -184,49 +201,72 @@ impl Dinosaur {
     /// rapid jump
     /// return true if the jump is done
     pub fn jump(&mut self) -> bool {
-        self.intern_hump(self.params.dinausor_jump_velocity as f64)
+        self.intern_hump(self.params.dinausor_jump_velocity)
     }
 
     /// bend
     pub fn bend(&mut self) -> bool {
         if self.is_bending {
-            return false;
+            false
         }else{
             self.is_bending = true;
             // switch height and width
             self.switch_width_height();
-            return true;
+            true
         }
     }
 
     /// unbend
     pub fn unbend(&mut self) -> bool {
         if !self.is_bending {
-            return false;
+            false
         }else{
             self.is_bending = false;
             // switch height and width
             self.switch_width_height();
-            return true;
+            true
+        }
+    }
+
+    /// do one action
+    pub fn do_action(&mut self, action : &NeuroneWebAction) {
+        match action {
+            NeuroneWebAction::Jump => {
+                self.jump();
+            },
+            NeuroneWebAction::Bend => {
+                self.bend();
+            },
+            NeuroneWebAction::Unbend => {
+                self.unbend();
+            },
+        }
+    }
+
+    /// do all the actions in the set
+    /// NOTE : the actions are done in a fixed order (the order of the HashSet change between two runs)
+    pub fn do_actions(&mut self, actions : HashSet<NeuroneWebAction>) {
+        let mut actions : Vec<NeuroneWebAction> = actions.into_iter().collect();
+        actions.sort();
+        for action in actions {
+            self.do_action(&action);
         }
     }
 
     fn switch_width_height(&mut self) {
-        let buf = self.width;
-        self.width = self.height;
-        self.height = buf;
+        std::mem::swap(&mut self.width, &mut self.height);
     }
 
 
     /// Update the position and apply the gravity
-    pub fn update(&mut self, tick: Instant) {
-        let delta = tick.duration_since(self.last_time_update).as_secs_f64();
+    pub fn update(&mut self, now: f64) {
+        let delta = now - self.last_time_update;
         self.y += self.velocity * delta;
         self.velocity -= self.params.gravity as f64 * delta;
         if self.y <= 0.0 {
             self.y = 0.0;
             self.velocity = 0.0;
         }
-        self.last_time_update = tick;
+        self.last_time_update = now;
     }
 }
\ No newline at end of file
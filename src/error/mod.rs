@@ -0,0 +1,102 @@
+use std::{fmt, io};
+
+/// the errors of the program
+#[derive(Debug)]
+pub enum Error {
+    /// a file can't be read
+    Read { path : String, source : io::Error },
+    /// a file can't be written (or a folder can't be created)
+    Write { path : String, source : io::Error },
+    /// the file to create already exists
+    FileExists { path : String },
+    /// a result can't be converted to json (what : the converted result)
+    Serialize { what : String, source : serde_json::Error },
+    /// the params (or the training config) file can't be parsed
+    ParamsParse { path : String, source : serde_json::Error },
+    /// the seed range of the evaluation is not like start..end
+    InvalidSeedRange { range : String },
+    /// the options of the command line give invalid params (see GameParameters::validate)
+    InvalidOptions { message : String },
+    /// the given params are different from the params of the training folder
+    ParamsMismatch { path : String, differences : Vec<String> },
+    /// the folder exists but isn't a training folder (no params.json)
+    MissingRunFolder { path : String },
+    /// a brain file can't be parsed
+    CorruptCheckpoint { path : String, source : serde_json::Error },
+    /// the brain file doesn't contain any brain
+    EmptyCheckpoint { path : String },
+    /// the index of the brain is not in the brain file
+    BrainIndexOutOfRange { path : String, index : usize, nb_brain : usize },
+    /// the brain to edit is a neural network (only the neurone webs can be edited)
+    NotEditable { path : String },
+    /// only the games of the neurone webs can be recorded (the neural networks are watched in a race)
+    NotRecordable { path : String },
+    /// there is no brain file that can be loaded in the training folder
+    NoCheckpoint { path : String },
+    /// a replay file can't be parsed
+    CorruptReplay { path : String, source : serde_json::Error },
+    /// the ui can't be run
+    Ui(iced::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// the exit code of the program for this error
+    /// (1 : ui, 2 : params or arguments, 3 : training folder, brain or replay file, 4 : read or write)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Ui(_) => 1,
+            Error::ParamsParse { .. } | Error::InvalidSeedRange { .. } | Error::InvalidOptions { .. } | Error::ParamsMismatch { .. } => 2,
+            Error::MissingRunFolder { .. } | Error::CorruptCheckpoint { .. } | Error::EmptyCheckpoint { .. } | Error::BrainIndexOutOfRange { .. } | Error::NotEditable { .. } | Error::NotRecordable { .. } | Error::NoCheckpoint { .. } | Error::CorruptReplay { .. } => 3,
+            Error::Read { .. } | Error::Write { .. } | Error::FileExists { .. } | Error::Serialize { .. } => 4,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read { path, source } => write!(f, "Unable to read {} ({}), check that the path exists and is readable", path, source),
+            Error::Write { path, source } => write!(f, "Unable to write {} ({}), check the permissions and the free space", path, source),
+            Error::FileExists { path } => write!(f, "The file {} already exists, add --force to replace it", path),
+            Error::Serialize { what, source } => write!(f, "Unable to convert the {} to json ({})", what, source),
+            Error::ParamsParse { path, source } => write!(f, "Unable to parse {} ({}), fix the json or remove the file to use the default values", path, source),
+            Error::InvalidSeedRange { range } => write!(f, "The seed range {} is invalid, give two numbers like 0..100 (the end excluded)", range),
+            Error::InvalidOptions { message } => write!(f, "The options give invalid params ({}), change --fps, --width or --height", message),
+            Error::ParamsMismatch { path, differences } => {
+                write!(f, "The given params are different from the params of the folder ({}), use the same params or a new folder to resume the training :", path)?;
+                for difference in differences {
+                    write!(f, "\n    {}", difference)?;
+                }
+                Ok(())
+            },
+            Error::MissingRunFolder { path } => write!(f, "{} is not a training folder (no params.json), give a new folder to create a training", path),
+            Error::CorruptCheckpoint { path, source } => write!(f, "The brain file {} is corrupted ({})", path, source),
+            Error::EmptyCheckpoint { path } => write!(f, "The brain file {} doesn't contain any brain", path),
+            Error::BrainIndexOutOfRange { path, index, nb_brain } => write!(f, "The brain file {} doesn't have a brain {} (only {} brains, from 0)", path, index, nb_brain),
+            Error::NotEditable { path } => write!(f, "Only the neurone webs can be edited by hand, {} holds neural networks (or the controller of the params is NeuralNetwork)", path),
+            Error::NotRecordable { path } => write!(f, "Only the games of the neurone webs can be recorded, {} holds neural networks (watch it without --record)", path),
+            Error::NoCheckpoint { path } => write!(f, "No valid brain file (brainN.json) in {}, remove the folder to restart the training", path),
+            Error::CorruptReplay { path, source } => write!(f, "The replay file {} is corrupted ({})", path, source),
+            Error::Ui(error) => write!(f, "The ui failed ({})", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Read { source, .. } | Error::Write { source, .. } => Some(source),
+            Error::ParamsParse { source, .. } | Error::Serialize { source, .. } | Error::CorruptCheckpoint { source, .. } | Error::CorruptReplay { source, .. } => Some(source),
+            Error::Ui(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<iced::Error> for Error {
+    fn from(error: iced::Error) -> Self {
+        Error::Ui(error)
+    }
+}
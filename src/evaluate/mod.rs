@@ -0,0 +1,285 @@
+use std::fs;
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::brain::{Brain, IntermediateResult};
+use crate::controller::Controller;
+use crate::entity::ObstacleEntityType;
+use crate::error::{Error, Result};
+use crate::network::NeuralNetwork;
+use crate::params::{GameParameters, FitnessAggregation, ControllerType};
+use crate::race::Race;
+
+/// the result of one game of a brain
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct GameEvaluation {
+    pub seed : String,
+    pub score : u64,
+    /// the simulated time before the death (in seconds)
+    pub survival_time : f64,
+    /// the obstacle that killed the dinosaur (None if the game reached the max score or the max tick)
+    pub death_cause : Option<ObstacleEntityType>,
+    /// the game was stopped at the max tick (the brain may never die)
+    pub capped : bool,
+}
+
+/// the results of a brain over all the seeds
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct BrainEvaluation {
+    /// the index of the brain in the brain file
+    pub brain_index : usize,
+    pub energy : f64,
+
+    pub mean_score : f64,
+    pub median_score : f64,
+    pub min_score : u64,
+    pub max_score : u64,
+    pub std_score : f64,
+    pub mean_survival_time : f64,
+
+    pub games : Vec<GameEvaluation>,
+}
+
+/// load the brains (or other controllers) of a brain file, or of a file with a single brain
+pub fn load_brains<C : Controller>(path : &str) -> Result<Vec<C>> {
+    let content = fs::read_to_string(path).map_err(|source| Error::Read { path : path.to_string(), source })?;
+    let brains = match serde_json::from_str::<IntermediateResult<C>>(&content) {
+        Ok(result) => result.brains,
+        Err(source) => match serde_json::from_str::<C>(&content) {
+            Ok(brain) => vec![brain],
+            Err(_) => return Err(Error::CorruptCheckpoint { path : path.to_string(), source }),
+        },
+    };
+    if brains.is_empty() {
+        return Err(Error::EmptyCheckpoint { path : path.to_string() });
+    }
+
+    Ok(brains)
+}
+
+/// the brains of a brain file, with their controller
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadedBrains {
+    NeuroneWeb(Vec<Brain>),
+    NeuralNetwork(Vec<NeuralNetwork>),
+}
+
+/// load the brains of a brain file (or of a single brain json) whatever their controller
+/// NOTE : the controller of the params is tried first, and its error is kept if the file can't be parsed as any controller
+pub fn load_any_brains(path : &str, params : &GameParameters) -> Result<LoadedBrains> {
+    let load_brain = || load_brains::<Brain>(path).map(LoadedBrains::NeuroneWeb);
+    let load_network = || load_brains::<NeuralNetwork>(path).map(LoadedBrains::NeuralNetwork);
+    match params.controller {
+        ControllerType::NeuroneWeb => load_with_fallback(load_brain, load_network),
+        ControllerType::NeuralNetwork => load_with_fallback(load_network, load_brain),
+    }
+}
+
+/// load with the first loader, then with the second if the file can't be parsed (the error of the first is kept)
+fn load_with_fallback<T>(first : impl FnOnce() -> Result<T>, second : impl FnOnce() -> Result<T>) -> Result<T> {
+    match first() {
+        Err(first_error @ Error::CorruptCheckpoint { .. }) => second().map_err(|error| match error {
+            Error::CorruptCheckpoint { .. } => first_error,
+            error => error,
+        }),
+        result => result,
+    }
+}
+
+/// play one game with the brain until it loses (or reaches the max score, or the max tick so that a perfect brain stops)
+/// NOTE : the game is a race with a single racer (same result as a game played alone, for every controller)
+pub fn evaluate_game<C : Controller>(params : &GameParameters, brain : &C, seed : &str, max_score : Option<u64>, max_tick : u64) -> GameEvaluation {
+    let mut race = Race::new(params, seed, vec![brain.clone()]);
+    while !race.is_over() && max_score.is_none_or(|max_score| race.land.score < max_score) && race.land.tick < max_tick {
+        race.step();
+    }
+    let racer = &race.racers[0];
+
+    GameEvaluation {
+        seed : seed.to_string(),
+        score : racer.score,
+        survival_time : race.land.get_tick_time(racer.nb_tick),
+        death_cause : racer.death_cause.clone(),
+        capped : !racer.has_lost && race.land.tick >= max_tick,
+    }
+}
+
+/// play the brain on every seed (in parallel) and summarize the scores
+pub fn evaluate_brain<C : Controller>(params : &GameParameters, brain_index : usize, brain : &C, seeds : &[String], max_score : Option<u64>, max_tick : u64) -> BrainEvaluation {
+    let games : Vec<GameEvaluation> = seeds
+        .par_iter()
+        .map(|seed| evaluate_game(params, brain, seed, max_score, max_tick))
+        .collect();
+    let scores : Vec<u64> = games.iter().map(|game| game.score).collect();
+    let mean_score = FitnessAggregation::Mean.aggregate(&scores);
+    let variance = if scores.is_empty() {
+        0.0
+    }else{
+        scores.iter().map(|score| (*score as f64 - mean_score).powi(2)).sum::<f64>() / scores.len() as f64
+    };
+
+    BrainEvaluation {
+        brain_index,
+        energy : brain.get_energie(params),
+        mean_score,
+        median_score : FitnessAggregation::Median.aggregate(&scores),
+        min_score : scores.iter().copied().min().unwrap_or(0),
+        max_score : scores.iter().copied().max().unwrap_or(0),
+        std_score : variance.sqrt(),
+        mean_survival_time : if games.is_empty() { 0.0 } else { games.iter().map(|game| game.survival_time).sum::<f64>() / games.len() as f64 },
+        games,
+    }
+}
+
+/// get the seeds to evaluate : the given seeds, then the seeds of the range ("start..end", the numbers as seeds)
+/// NOTE : without seed, the land seed of the params is used
+pub fn get_seeds(params : &GameParameters, seeds : &[String], seed_range : Option<&str>) -> Result<Vec<String>> {
+    let mut all_seeds = seeds.to_vec();
+    if let Some(range) = seed_range {
+        let invalid = || Error::InvalidSeedRange { range : range.to_string() };
+        let (start, end) = range.split_once("..").ok_or_else(invalid)?;
+        let start = start.trim().parse::<u64>().map_err(|_| invalid())?;
+        let end = end.trim().parse::<u64>().map_err(|_| invalid())?;
+        if start >= end {
+            return Err(invalid());
+        }
+        all_seeds.extend((start..end).map(|i| i.to_string()));
+    }
+    if all_seeds.is_empty() {
+        all_seeds.push(params.land_seed.clone());
+    }
+    Ok(all_seeds)
+}
+
+/// print the evaluations as a table
+fn print_table(evaluations : &[BrainEvaluation]) {
+    for evaluation in evaluations {
+        println!(
+            "brain {} (energy {:.0}) : mean {:.2}, median {:.1}, min {}, max {}, std {:.2}, mean survival {:.1} s",
+            evaluation.brain_index, evaluation.energy, evaluation.mean_score, evaluation.median_score,
+            evaluation.min_score, evaluation.max_score, evaluation.std_score, evaluation.mean_survival_time
+        );
+        println!("    {:<20} {:>8} {:>12} {:>22}", "seed", "score", "survival (s)", "death cause");
+        for game in &evaluation.games {
+            let death_cause = match &game.death_cause {
+                Some(death_cause) => death_cause.to_string(),
+                None if game.capped => "capped (max tick)".to_string(),
+                None => "max score".to_string(),
+            };
+            println!("    {:<20} {:>8} {:>12.1} {:>22}", game.seed, game.score, game.survival_time, death_cause);
+        }
+    }
+}
+
+/// evaluate every brain of the brain file on the seeds and print the results (table or json)
+/// NOTE : the file can hold neurone webs or neural networks (see ControllerType)
+pub fn evaluate_pipeline(brain_path : &str, params : &GameParameters, seeds : &[String], seed_range : Option<&str>, max_score : Option<u64>, max_tick : u64, json : bool) -> Result<()> {
+    match load_any_brains(brain_path, params)? {
+        LoadedBrains::NeuroneWeb(brains) => evaluate_all(&brains, params, seeds, seed_range, max_score, max_tick, json),
+        LoadedBrains::NeuralNetwork(networks) => evaluate_all(&networks, params, seeds, seed_range, max_score, max_tick, json),
+    }
+}
+
+/// evaluate the brains on the seeds and print the results (table or json)
+fn evaluate_all<C : Controller>(brains : &[C], params : &GameParameters, seeds : &[String], seed_range : Option<&str>, max_score : Option<u64>, max_tick : u64, json : bool) -> Result<()> {
+    let seeds = get_seeds(params, seeds, seed_range)?;
+
+    let evaluations : Vec<BrainEvaluation> = brains.iter()
+        .enumerate()
+        .map(|(i, brain)| evaluate_brain(params, i, brain, &seeds, max_score, max_tick))
+        .collect();
+
+    if json {
+        let json = serde_json::to_string_pretty(&evaluations).map_err(|source| Error::Serialize { what : "evaluations".to_string(), source })?;
+        println!("{}", json);
+    }else{
+        print_table(&evaluations);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::game::Game;
+    use crate::utils::{str_to_u8_array, write_json, get_test_path};
+
+    use super::*;
+
+    #[test]
+    fn test_get_seeds() {
+        let params = GameParameters::new_default();
+        assert_eq!(get_seeds(&params, &[], None).unwrap(), vec![params.land_seed.clone()]);
+        assert_eq!(get_seeds(&params, &["a".to_string()], Some("3..5")).unwrap(), vec!["a", "3", "4"]);
+        // a malformed range is an error, not the land seed
+        for range in ["abc", "5..x", "5..3", "..4"] {
+            assert!(matches!(get_seeds(&params, &[], Some(range)), Err(Error::InvalidSeedRange { .. })), "{}", range);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_brain() {
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brain = Brain::new(&params, &mut rng);
+        let seeds = get_seeds(&params, &[], Some("0..6")).unwrap();
+
+        let evaluation = evaluate_brain(&params, 0, &brain, &seeds, None, u64::MAX);
+        assert_eq!(evaluation.games.len(), 6);
+        for (game, seed) in evaluation.games.iter().zip(&seeds) {
+            // same result as a game played alone
+            assert_eq!(game, &evaluate_game(&params, &brain, seed, None, u64::MAX));
+            assert!(game.death_cause.is_some() && !game.capped);
+            // same result as the game of the window
+            let mut game_alone = Game::new(&params, seed, Some(brain.clone()), None);
+            while !game_alone.has_lost {
+                game_alone.step();
+            }
+            assert_eq!(game.score, game_alone.score);
+            assert_eq!(game.survival_time, game_alone.get_tick_time(game_alone.tick));
+        }
+        assert!(evaluation.min_score as f64 <= evaluation.mean_score && evaluation.mean_score <= evaluation.max_score as f64);
+
+        // the max score stop the game without death
+        let evaluation = evaluate_brain(&params, 0, &brain, &seeds, Some(0), u64::MAX);
+        assert!(evaluation.games.iter().all(|game| game.score == 0 && game.death_cause.is_none() && !game.capped));
+
+        // the max tick stop the game of a brain that never dies
+        let empty_brain = Brain { neurone_web : Vec::new() };
+        let game = evaluate_game(&params, &empty_brain, "abc", None, 10);
+        assert!(game.capped && game.death_cause.is_none());
+        assert_eq!(game.survival_time, Game::new(&params, "abc", None, None).get_tick_time(10));
+    }
+
+    #[test]
+    fn test_load_brains() {
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brain = Brain::new(&params, &mut rng);
+        let path = get_test_path("single_brain.json");
+        write_json(&path, &brain).unwrap();
+        let brains : Vec<Brain> = load_brains(&path).unwrap();
+        assert_eq!(brains, vec![brain.clone()]);
+
+        // the controller is found whatever the params
+        let mut network_params = params.clone();
+        network_params.controller = ControllerType::NeuralNetwork;
+        assert_eq!(load_any_brains(&path, &network_params).unwrap(), LoadedBrains::NeuroneWeb(vec![brain]));
+        let network = NeuralNetwork::new(&network_params, &mut rng);
+        write_json(&path, &network).unwrap();
+        assert_eq!(load_any_brains(&path, &params).unwrap(), LoadedBrains::NeuralNetwork(vec![network]));
+
+        // an invalid file gives the parse error of the controller of the params
+        fs::write(&path, "{\"brains\" : [{\"neurone_web\" : 5}]}").unwrap();
+        let brain_error = load_brains::<Brain>(&path).unwrap_err().to_string();
+        assert_eq!(load_any_brains(&path, &params).unwrap_err().to_string(), brain_error);
+        let network_error = load_brains::<NeuralNetwork>(&path).unwrap_err().to_string();
+        assert_eq!(load_any_brains(&path, &network_params).unwrap_err().to_string(), network_error);
+        assert_ne!(brain_error, network_error);
+        fs::remove_file(&path).unwrap();
+    }
+}
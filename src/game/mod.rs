@@ -1,90 +1,316 @@
 
 use std::collections::HashSet;
-use std::time::{Instant, Duration};
 
 use rand::{SeedableRng, Rng};
 
 use iced::widget::canvas::{Cursor, Geometry, Cache, Path, Stroke, LineCap, LineJoin, Frame, Text};
-use iced::widget::{canvas, Canvas};
-use iced::theme::{Theme};
+use iced::widget::canvas;
+use iced::theme::Theme;
 use iced::{Application, executor, Command, Rectangle, Size, Color, Point, Subscription, keyboard};
 use rand_pcg::Pcg64;
+use serde::{Serialize, Deserialize};
 
 use crate::brain::Brain;
 use crate::entity::{Dinosaur, Obstacle, ObstacleGenerateType, ObstacleEntityType};
-use crate::neurone::{Neurone, NeuroneWebAction, get_color_from_neurone, NeuroneActivationCondition, NeuroneActivation, get_color_from_activation, get_color_from_action};
-use crate::params::{GameParameters};
-use crate::utils::{str_to_u8_array, get_scale_value, check_collision, remove_indexes};
-
-
-
+use crate::neurone::{Neurone, NeuroneWeb, NeuroneWebState, NeuroneWebAction, get_color_from_neurone, NeuroneActivationCondition, NeuroneActivation, NeuroneAnchor, get_color_from_activation, get_color_from_action};
+use crate::params::GameParameters;
+use crate::replay::{Replay, ReplayAction};
+use crate::sprite::{SPRITES, GameView, SpriteScene};
+use crate::error::{Error, Result};
+use crate::utils::{str_to_u8_array, get_scale_value, check_collision, remove_indexes, write_json};
+
+
+/// the state of one game
+/// NOTE : the game doesn't depend on any clock, the time is simulated with a tick counter
+/// (one tick is 1/game_fps seconds), so a game can be replayed or serialized exactly
+#[derive(Serialize, Deserialize)]
 pub struct Game {
     pub dinosaur: Dinosaur,
     pub obstacles: Vec<Obstacle>,
     pub score: u64,
 
     pub has_lost : bool,
+    /// the type of the obstacle that hit the dinosaur
+    #[serde(default)]
+    pub death_cause : Option<ObstacleEntityType>,
 
     params : GameParameters,
+    /// the obstacles that can be generated (depending on the commands, calculated at the creation of the game)
+    obstacle_generate_types : Vec<ObstacleGenerateType>,
     // ------ timing ------
-    /// last time the game was updated
-    pub last_time_update: Instant, 
-    /// next time an obstacle will be generated
-    pub next_obstacle_time: Instant, 
-     /// time when the game started
-    pub game_start_time: Instant,
+    /// number of tick simulated since the start of the game
+    pub tick: u64,
+    /// last simulated time the game was updated (in seconds since the start of the game)
+    pub last_time_update: f64,
+    /// next simulated time an obstacle will be generated (in seconds since the start of the game)
+    pub next_obstacle_time: f64,
 
     // ------ rng ------
+    #[serde(default)]
+    pub land_seed : String,
     pub land_rng : Pcg64,
 
     // ------ auto play ------
     pub brain : Option<Brain>,
 
+    // ------ record ------
+    /// the actions of the player since the start of the game
+    #[serde(default)]
+    pub actions : Vec<ReplayAction>,
+    /// the file where the replay is saved when the game is lost
+    #[serde(skip)]
+    record_path : Option<String>,
+
     // ------ display ------
+    #[serde(skip)]
     cache: Option<Cache>,
+    /// the replay played (only in replay mode)
+    #[serde(skip)]
+    replay : Option<Replay>,
+    #[serde(skip)]
+    playback : Playback,
+    /// draw the hitboxes instead of the sprites (to debug)
+    #[serde(skip)]
+    show_hitbox : bool,
+    /// the best scores (only in the play and brain modes)
+    #[serde(skip)]
+    high_scores : Option<HighScores>,
+    /// the brains of the brain file that can be played in turn (only in the brain mode)
+    #[serde(skip)]
+    brain_selection : Option<BrainSelection>,
+    /// the games of the other brains, played on the same land and drawn as ghosts
+    #[serde(skip)]
+    ghosts : Vec<Game>,
+}
+
+/// the name of the folder of the game in the data folder of the user
+const DATA_FOLDER_NAME: &str = "chrome_offline_game";
+
+/// get the file where the best scores are saved between two runs, in the data folder of the user
+/// ($XDG_DATA_HOME, ~/.local/share or %APPDATA%)
+/// NOTE : without data folder, the high scores are not kept
+pub fn get_high_scores_path() -> Option<String> {
+    let data_folder = std::env::var("XDG_DATA_HOME").ok().filter(|folder| !folder.is_empty())
+        .or_else(|| std::env::var("HOME").ok().map(|home| format!("{}/.local/share", home)))
+        .or_else(|| std::env::var("APPDATA").ok())?;
+    Some(format!("{}/{}/high_scores.json", data_folder, DATA_FOLDER_NAME))
+}
+
+/// the best scores of the player and of the brains
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct HighScores {
+    pub play : u64,
+    pub brain : u64,
+}
+
+impl HighScores {
+    /// load the best scores (zero if the file doesn't exist or is invalid)
+    pub fn new_from_file(path : &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// save the best scores (the folder of the file is created if needed)
+    pub fn save(&self, path : &str) -> Result<()> {
+        if let Some(folder) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(folder).map_err(|source| Error::Write { path : path.to_string(), source })?;
+        }
+        write_json(path, self)
+    }
+
+    /// get the best score of the mode (brain or player)
+    pub fn get(&self, is_brain : bool) -> u64 {
+        if is_brain { self.brain } else { self.play }
+    }
+
+    /// update the best score of the mode, return true if it is beaten
+    pub fn update(&mut self, is_brain : bool, score : u64) -> bool {
+        let high_score = if is_brain { &mut self.brain } else { &mut self.play };
+        if score > *high_score {
+            *high_score = score;
+            true
+        }else{
+            false
+        }
+    }
+}
+
+/// the brains of a brain file (the tied best brains of a generation) that can be played in turn
+#[derive(Debug, Clone)]
+pub struct BrainSelection {
+    brains : Vec<Brain>,
+    /// the index of the played brain
+    index : usize,
+    /// play the other brains at the same time as ghosts
+    with_ghosts : bool,
+}
+
+impl BrainSelection {
+    /// WARN : the index must be in the brains
+    pub fn new(brains : Vec<Brain>, index : usize, with_ghosts : bool) -> Self {
+        Self {
+            brains,
+            index,
+            with_ghosts,
+        }
+    }
+
+    /// a selection of only one brain
+    pub fn new_single(brain : Brain) -> Self {
+        Self::new(vec![brain], 0, false)
+    }
+
+    pub fn get_brain(&self) -> &Brain {
+        &self.brains[self.index]
+    }
+
+    /// select the next brain (or the previous one with a negative offset), back to the first after the last
+    pub fn cycle(&mut self, offset : i64) {
+        self.index = (self.index as i64 + offset).rem_euclid(self.brains.len() as i64) as usize;
+    }
+
+    /// get the brains played as ghosts (all the brains except the selected one)
+    fn get_ghost_brains(&self) -> Vec<Brain> {
+        if !self.with_ghosts {
+            return Vec::new();
+        }
+        self.brains.iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.index)
+            .map(|(_, brain)| brain.clone())
+            .collect()
+    }
+}
+
+/// the color of the ghost dinosaurs (semi-transparent)
+const GHOST_COLOR: Color = Color { r: 0.2, g: 0.4, b: 0.9, a: 0.35 };
+
+/// the minimum and maximum speed of the simulation in the ui
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 8.0;
+
+/// the control of the simulation speed in the ui
+pub struct Playback {
+    paused : bool,
+    /// the number of tick simulated at each update of the ui (between 0.25 and 8)
+    speed : f64,
+    /// the ticks to simulate not done yet (for the speed under 1)
+    tick_budget : f64,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            paused : false,
+            speed : 1.0,
+            tick_budget : 0.0,
+        }
+    }
+}
+
+impl Playback {
+    /// get the number of tick to simulate at this update of the ui (none in pause)
+    pub fn next_nb_tick(&mut self) -> u64 {
+        if self.paused {
+            return 0;
+        }
+        self.tick_budget += self.speed;
+        let nb_tick = self.tick_budget.floor();
+        self.tick_budget -= nb_tick;
+        nb_tick as u64
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// multiply the speed (clamped between 0.25 and 8)
+    pub fn change_speed(&mut self, factor : f64) {
+        self.speed = (self.speed * factor).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// get the text of the state of the playback
+    pub fn get_status(&self) -> String {
+        format!(
+            "x{}{} ([p] pause, [n] step, [+] [-] speed)",
+            self.speed, if self.paused { " paused" } else { "" }
+        )
+    }
 }
 
 impl Game {
-    pub fn new(params : &GameParameters, now : Instant, seed: &str, brain : Option<Brain>, cache : Option<Cache>) -> Self {
-        let me = Self {
-            dinosaur: Dinosaur::new_dinosaur(params, now),
+    pub fn new(params : &GameParameters, seed: &str, brain : Option<Brain>, cache : Option<Cache>) -> Self {
+        Self {
+            dinosaur: Dinosaur::new_dinosaur(params, 0.0),
             obstacles: Vec::new(),
             score: 0,
             has_lost : false,
-            last_time_update : now,
-            next_obstacle_time : now,
-            game_start_time : now,
+            death_cause : None,
+            tick : 0,
+            last_time_update : 0.0,
+            next_obstacle_time : 0.0,
+            land_seed : seed.to_string(),
             land_rng : Pcg64::from_seed(str_to_u8_array(seed)),
             brain,
+            actions : Vec::new(),
+            record_path : None,
             cache,
+            obstacle_generate_types : params.get_obstacles_generation_type(),
+            replay : None,
+            playback : Playback::default(),
+            show_hitbox : false,
+            high_scores : None,
+            brain_selection : None,
+            ghosts : Vec::new(),
             params : params.clone(),
-        };
-        me
+        }
+    }
+
+    /// create the game of the selected brain, with the other brains as ghosts if asked
+    /// NOTE : the obstacles only depend on the land seed, so all the games have the same obstacles
+    pub fn new_from_selection(params : &GameParameters, selection : BrainSelection, cache : Option<Cache>) -> Self {
+        let mut game = Self::new(params, params.land_seed.as_str(), Some(selection.get_brain().clone()), cache);
+        game.ghosts = selection.get_ghost_brains()
+            .into_iter()
+            .map(|brain| Self::new(params, params.land_seed.as_str(), Some(brain), None))
+            .collect();
+        game.brain_selection = Some(selection);
+        game
     }
     // ---------------- game state ----------------
 
-    pub fn update(&mut self, now: Instant) {
+    /// get the simulated time of a tick (in seconds since the start of the game)
+    pub fn get_tick_time(&self, tick : u64) -> f64 {
+        tick as f64 / self.params.game_fps as f64
+    }
+
+    /// advance the game of one tick (do nothing if the game is lost)
+    pub fn step(&mut self) {
+        if self.has_lost {
+            return;
+        }
+        self.tick += 1;
+        self.update(self.get_tick_time(self.tick));
+    }
+
+    /// update the game to the simulated time now (in seconds since the start of the game)
+    fn update(&mut self, now: f64) {
         self.dinosaur.update(now);
 
         // update all obstacle
         self.update_all_obstacles(now);
 
         // check the hitbox
-        for obstacle in self.obstacles.iter() {
-            if check_collision(
-                self.dinosaur.x,
-                self.dinosaur.y,
-                self.dinosaur.width,
-                self.dinosaur.height,
-
-                obstacle.x,
-                obstacle.y,
-                obstacle.width,
-                obstacle.height
-            ){
-                self.has_lost = true;
-                return;
-            }
+        if let Some(obstacle) = self.get_collision(&self.dinosaur) {
+            self.death_cause = Some(obstacle.type_.clone());
+            self.has_lost = true;
+            return;
         }
 
         // calculate the next obstacle if we have passed the time
@@ -97,35 +323,151 @@ impl Game {
         self.last_time_update = now;
     }
 
+    /// advance only the obstacles of one tick, without any dinosaur (see Race), return the simulated time
+    /// NOTE : the obstacles only depend on the land seed, so they are the same as in a game with a dinosaur
+    pub fn step_land(&mut self) -> f64 {
+        self.tick += 1;
+        let now = self.get_tick_time(self.tick);
+        self.update_all_obstacles(now);
+        if now >= self.next_obstacle_time {
+            self.generate_next_obstacle();
+        }
+        self.last_time_update = now;
+        now
+    }
+
+    /// get the obstacle that hit the dinosaur (if there is one)
+    pub fn get_collision(&self, dinosaur : &Dinosaur) -> Option<&Obstacle> {
+        self.obstacles.iter().find(|obstacle| check_collision(
+            dinosaur.x,
+            dinosaur.y,
+            dinosaur.width,
+            dinosaur.height,
+
+            obstacle.x,
+            obstacle.y,
+            obstacle.width,
+            obstacle.height
+        ))
+    }
+
     // -----------------    actions    -----------------
-    /// do one action
-    fn do_action(&mut self, action : &NeuroneWebAction) {
-        match action {
-            NeuroneWebAction::Jump => {
-                self.dinosaur.jump();
-            },
-            NeuroneWebAction::Bend => {
-                self.dinosaur.bend();
-            },
-            NeuroneWebAction::Unbend => {
-                self.dinosaur.unbend();
+    /// do all the actions of the brain
+    pub fn do_actions(&mut self, actions : HashSet<NeuroneWebAction>) {
+        self.dinosaur.do_actions(actions);
+    }
+
+    /// do an action of the player (recorded for the replay)
+    pub fn do_player_action(&mut self, action : &NeuroneWebAction) {
+        if self.has_lost {
+            return;
+        }
+        self.actions.push(ReplayAction { tick : self.tick, action : action.clone() });
+        self.dinosaur.do_action(action);
+    }
+
+    /// get the replay of the game since its start
+    pub fn to_replay(&self) -> Replay {
+        Replay {
+            params : self.params.clone(),
+            land_seed : self.land_seed.clone(),
+            brain : self.brain.clone(),
+            actions : self.actions.clone(),
+            nb_tick : self.tick,
+            score : self.score,
+        }
+    }
+
+    /// advance the game of one tick in the ui (with the actions of the replay), and save the record if the game is lost
+    fn advance(&mut self) {
+        let was_lost = self.has_lost;
+        // the ghosts stop with the game
+        if !was_lost {
+            for ghost in &mut self.ghosts {
+                ghost.step();
+            }
+        }
+        match self.replay.take() {
+            Some(replay) => {
+                replay.step(self);
+                self.replay = Some(replay);
             },
+            None => self.step(),
+        }
+        if !was_lost && self.has_lost {
+            self.save_record();
+            self.save_high_score();
         }
     }
 
-    /// doall the actions in the set
-    pub fn do_actions(&mut self, actions : HashSet<NeuroneWebAction>) {
-        for action in actions {
-            self.do_action(&action);
+    /// save the score if it beats the best score of the mode
+    fn save_high_score(&mut self) {
+        let is_brain = self.brain.is_some();
+        if let Some(high_scores) = &mut self.high_scores {
+            if high_scores.update(is_brain, self.score) {
+                if let Some(Err(error)) = get_high_scores_path().map(|path| high_scores.save(&path)) {
+                    println!("Warning : {}", error);
+                }
+            }
+        }
+    }
+
+    /// get the lines of the head-up display
+    fn get_hud_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Score : {}", self.score),
+            format!("Time : {:.1} s", self.get_tick_time(self.tick)),
+            format!("Obstacle interval : {:.2} s", self.get_next_obstacle_timing(0.0, self.score)),
+            format!("Land seed : {}", self.land_seed),
+        ];
+        if let Some(brain) = &self.brain {
+            lines.push(format!("Brain energy : {:.0}", brain.get_energie(&self.params)));
+        }
+        if let Some(selection) = &self.brain_selection {
+            if selection.brains.len() > 1 {
+                lines.push(format!("Brain : {}/{} ([b] next)", selection.index + 1, selection.brains.len()));
+            }
+        }
+        if !self.ghosts.is_empty() {
+            let nb_alive = self.ghosts.iter().filter(|ghost| !ghost.has_lost).count();
+            lines.push(format!("Ghosts alive : {}/{}", nb_alive, self.ghosts.len()));
+        }
+        if let Some(high_scores) = &self.high_scores {
+            lines.push(format!("High score : {}", high_scores.get(self.brain.is_some())));
+        }
+        lines
+    }
+
+    /// replace the game by a new one, keeping the state of the ui (replay, record, speed)
+    fn replace(&mut self, game : Game) {
+        let record_path = self.record_path.take();
+        let replay = self.replay.take();
+        let playback = std::mem::take(&mut self.playback);
+        let show_hitbox = self.show_hitbox;
+        let high_scores = self.high_scores.take();
+        *self = game;
+        self.high_scores = high_scores;
+        self.record_path = record_path;
+        self.replay = replay;
+        self.playback = playback;
+        self.show_hitbox = show_hitbox;
+    }
+
+    /// save the replay of the game if it is recorded
+    fn save_record(&self) {
+        if let Some(record_path) = &self.record_path {
+            match self.to_replay().save(record_path) {
+                Ok(()) => println!("The game is recorded in {}", record_path),
+                Err(error) => println!("Warning : {}", error),
+            }
         }
     }
 
     /// do all the actions of the brain (if there is one)
     fn get_brain_actions(&self) -> HashSet<NeuroneWebAction> {
-        if self.brain.is_none() {
-            HashSet::new()
-        }else{
-            self.brain.as_ref().unwrap().get_activations(&self.obstacles)
+        match &self.brain {
+            Some(brain) => brain.get_activations(&self.obstacles, &self.dinosaur, self.get_speed()),
+            None => HashSet::new(),
         }
     }
 
@@ -133,8 +475,17 @@ impl Game {
 
     // ................. obstacle : 
 
+    /// get the speed of the game, from 0 at the start to 1 when the obstacles come at the maximum rate (it increases with the score)
+    pub fn get_speed(&self) -> f64 {
+        let range = self.params.max_obstacle_generation_time - self.params.min_obstacle_generation_time;
+        if range <= 0.0 {
+            return 1.0;
+        }
+        (self.params.max_obstacle_generation_time - self.get_next_obstacle_timing(0.0, self.score)) / range
+    }
+
     /// get the timing for the next obstacle (accelerate 4 times)
-    fn get_next_obstacle_timing(&self, last_time : Instant, score : u64) -> Instant {
+    fn get_next_obstacle_timing(&self, last_time : f64, score : u64) -> f64 {
         let interval = get_scale_value(
             self.params.max_obstacle_generation_time,
             self.params.min_obstacle_generation_time,
@@ -143,10 +494,9 @@ impl Game {
             self.params.score_increase_speed_interval,
             true
         );
-        
-        last_time.checked_add(
-            Duration::from_millis((interval * 1000.0) as u64)// apply the coef with reverse it
-        ).unwrap()// WARN : error if the duration is too big
+
+        // NOTE : the interval is truncated to the millisecond (as with the old Instant clock), so the lands stay the same
+        last_time + (interval * 1000.0) as u64 as f64 / 1000.0
     }
 
     /// generate the next obstacle (add it to the vector) and update the timing
@@ -157,8 +507,8 @@ impl Game {
         );
         let x = self.params.game_width as f64 + self.params.pterodactyle_offset_with_rock as f64;
         
-        let random_obstacle_index = self.land_rng.gen_range(0..self.params.obstacle_generate_types.len());
-        let random_obstacle: ObstacleGenerateType = self.params.obstacle_generate_types[random_obstacle_index].clone();
+        let random_obstacle_index = self.land_rng.gen_range(0..self.obstacle_generate_types.len());
+        let random_obstacle: ObstacleGenerateType = self.obstacle_generate_types[random_obstacle_index].clone();
 
         match random_obstacle {
             ObstacleGenerateType::Cactus => {
@@ -226,7 +576,7 @@ impl Game {
     }
 
     /// update all the obstacle and remove (add 1 to the score) the obstacle outside the screen
-    fn update_all_obstacles(&mut self, now : Instant) {
+    fn update_all_obstacles(&mut self, now : f64) {
         
         let mut to_remove : Vec<usize> = Vec::new();
         for (i, obstacle) in self.obstacles.iter_mut().enumerate() {
@@ -242,12 +592,21 @@ impl Game {
     }
 
     // ............... display :
+    pub fn get_params(&self) -> &GameParameters {
+        &self.params
+    }
+
     /// symetric of the point with the height
     fn get_opposite(&self, point : Point, height : f32) -> Point {
-        Point {
-            x : point.x,
-            y : (self.params.game_height as f32) - point.y - height
-        }
+        get_opposite(&self.params, point, height)
+    }
+}
+
+/// symetric of the point with the height (the game y goes up, the screen y goes down)
+pub fn get_opposite(params : &GameParameters, point : Point, height : f32) -> Point {
+    Point {
+        x : point.x,
+        y : (params.game_height as f32) - point.y - height
     }
 }
 
@@ -255,21 +614,36 @@ impl Game {
 #[derive(Debug, Clone)]
 pub enum Message {
     Do(NeuroneWebAction),
-    Restart(Option<Brain>, GameParameters),
+    Restart,
     Update,
+    // ------ playback ------
+    TogglePause,
+    /// advance the game of one tick
+    Step,
+    /// multiply the speed (clamped between 0.25 and 8)
+    ChangeSpeed(f64),
+    /// move in the replay of a number of tick
+    Seek(i64),
+    /// switch between the sprites and the hitboxes
+    ToggleHitbox,
+    /// play the next brain of the brain file (or the previous one with a negative offset)
+    ChangeBrain(i64),
 }
 
 #[derive(Debug, Clone)]
 pub enum CustomFlags {
-    Play(GameParameters),
-    Brain(Brain, GameParameters),
+    /// the params and the file to record the game
+    Play(GameParameters, Option<String>),
+    /// the brains (with the played one), the params and the file to record the game
+    Brain(BrainSelection, GameParameters, Option<String>),
+    Replay(Box<Replay>),
 }
 
 // define the default value for the flags
 impl Default for CustomFlags {
     fn default() -> Self {
         // Define the default values for CustomFlags here
-        CustomFlags::Play(GameParameters::new_default())
+        CustomFlags::Play(GameParameters::new_default(), None)
     }
 }
 
@@ -286,10 +660,23 @@ impl Application for Game {
             
             // construct the game at the beginning
             match flags {
-                CustomFlags::Play(params) => {
-                    Self::new(&params, Instant::now(), params.land_seed.as_str(), None, Some(Default::default()))
+                CustomFlags::Play(params, record_path) => {
+                    let mut game = Self::new(&params, params.land_seed.as_str(), None, Some(Default::default()));
+                    game.record_path = record_path;
+                    game.high_scores = Some(get_high_scores_path().map(|path| HighScores::new_from_file(&path)).unwrap_or_default());
+                    game
                 }
-                CustomFlags::Brain(brain, params) => Self::new(&params, Instant::now(), params.land_seed.as_str(), Some(brain), Some(Default::default())),
+                CustomFlags::Brain(selection, params, record_path) => {
+                    let mut game = Self::new_from_selection(&params, selection, Some(Default::default()));
+                    game.record_path = record_path;
+                    game.high_scores = Some(get_high_scores_path().map(|path| HighScores::new_from_file(&path)).unwrap_or_default());
+                    game
+                },
+                CustomFlags::Replay(replay) => {
+                    let mut game = replay.get_game(Some(Default::default()));
+                    game.replay = Some(*replay);
+                    game
+                },
             },
             Command::none(),
         )
@@ -303,40 +690,81 @@ impl Application for Game {
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
         match message {
             Message::Do(action) => {
-                if !self.has_lost {
-                    self.do_action(&action);
-                }
+                self.do_player_action(&action);
                 Command::none()
             },
             Message::Update => {
-                let now = self.last_time_update.checked_add(
-                    Duration::from_nanos(1000_000_000/self.params.game_fps as u64)
-                ).unwrap();
-
-                if !self.has_lost {
-                    self.update(now);
+                if self.playback.is_paused() {
+                    return Command::none();
+                }
+                // simulate the number of tick of the speed
+                for _ in 0..self.playback.next_nb_tick() {
+                    self.advance();
                 }
-               
+
                 // don't forget to clear the cache to force the redraw
                 self.cache.as_ref().unwrap().clear();
                 Command::none()
             },
-            Message::Restart(brain, params) => {
-                *self = Self::new(&params, Instant::now(), params.land_seed.as_str(), brain, Some(Default::default()));
+            Message::Restart => {
+                if self.replay.is_some() {
+                    return Application::update(self, Message::Seek(-(self.tick as i64)));
+                }
+                let params = self.params.clone();
+                let game = match self.brain_selection.take() {
+                    Some(selection) => Self::new_from_selection(&params, selection, Some(Default::default())),
+                    None => Self::new(&params, params.land_seed.as_str(), self.brain.take(), Some(Default::default())),
+                };
+                self.replace(game);
+                Command::none()
+            },
+            Message::ChangeBrain(offset) => {
+                // the game restart with the next brain
+                if let Some(mut selection) = self.brain_selection.take() {
+                    selection.cycle(offset);
+                    let game = Self::new_from_selection(&self.params.clone(), selection, Some(Default::default()));
+                    self.replace(game);
+                }
+                Command::none()
+            },
+            Message::TogglePause => {
+                self.playback.toggle_pause();
+                self.cache.as_ref().unwrap().clear();
+                Command::none()
+            },
+            Message::Step => {
+                self.advance();
+                self.cache.as_ref().unwrap().clear();
+                Command::none()
+            },
+            Message::ChangeSpeed(factor) => {
+                self.playback.change_speed(factor);
+                self.cache.as_ref().unwrap().clear();
+                Command::none()
+            },
+            Message::ToggleHitbox => {
+                self.show_hitbox = !self.show_hitbox;
+                self.cache.as_ref().unwrap().clear();
+                Command::none()
+            },
+            Message::Seek(nb_tick) => {
+                if let Some(replay) = &self.replay {
+                    // the game is simulated again from the start
+                    let tick = (self.tick as i64 + nb_tick).clamp(0, replay.nb_tick as i64) as u64;
+                    let game = replay.simulate_to(tick, Some(Default::default()));
+                    self.replace(game);
+                }
                 Command::none()
             },
         }
     }
 
     fn view(&self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
-        Canvas::new(self)
-            .width(self.params.game_width)
-            .height(self.params.game_height)
-            .into()
+        GameView::new(self).into()
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced::time::every(std::time::Duration::from_nanos(1000_000_000/self.params.game_fps as u64)).map(|_| {
+        iced::time::every(std::time::Duration::from_nanos(1_000_000_000/self.params.game_fps as u64)).map(|_| {
             Message::Update
         })
     }
@@ -355,12 +783,50 @@ impl canvas::Program<Message> for Game {
             _cursor: Cursor,
         ) -> (canvas::event::Status, Option<Message>) {
         match event {
+            // the controls of the playback (in all the modes)
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('p')) => {
+                (canvas::event::Status::Captured, Some(Message::TogglePause))
+            },
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('n')) => {
+                (canvas::event::Status::Captured, Some(Message::Step))
+            },
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('+')) => {
+                (canvas::event::Status::Captured, Some(Message::ChangeSpeed(2.0)))
+            },
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('-')) => {
+                (canvas::event::Status::Captured, Some(Message::ChangeSpeed(0.5)))
+            },
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('h')) => {
+                (canvas::event::Status::Captured, Some(Message::ToggleHitbox))
+            },
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('b')) if self.brain_selection.is_some() => {
+                (canvas::event::Status::Captured, Some(Message::ChangeBrain(1)))
+            },
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('B')) if self.brain_selection.is_some() => {
+                (canvas::event::Status::Captured, Some(Message::ChangeBrain(-1)))
+            },
+            canvas::Event::Keyboard(keyboard_event) if self.replay.is_some() => {
+                // the controls of the replay
+                let one_second = self.params.game_fps as i64;
+                match keyboard_event {
+                    keyboard::Event::CharacterReceived(' ') if self.has_lost => {
+                        (canvas::event::Status::Captured, Some(Message::Restart))
+                    },
+                    keyboard::Event::KeyPressed { key_code : keyboard::KeyCode::Left, .. } => {
+                        (canvas::event::Status::Captured, Some(Message::Seek(-one_second)))
+                    },
+                    keyboard::Event::KeyPressed { key_code : keyboard::KeyCode::Right, .. } => {
+                        (canvas::event::Status::Captured, Some(Message::Seek(one_second)))
+                    },
+                    _ => (canvas::event::Status::Ignored, None)
+                }
+            },
             canvas::Event::Keyboard(keyboard_event) => {
                 match keyboard_event {
                     keyboard::Event::CharacterReceived(' ') => {
                         if self.has_lost {
                             // restart the game
-                            return (canvas::event::Status::Captured, Some(Message::Restart(self.brain.clone(), self.params.clone())))
+                            return (canvas::event::Status::Captured, Some(Message::Restart))
                         }
                         // unbend if bending
                         if self.dinosaur.is_bending {
@@ -392,124 +858,254 @@ impl canvas::Program<Message> for Game {
             if self.has_lost {
                 frame.fill_text(format!("Lost (press space to restart): {}", self.score));
             }else{
-                // draw the dinosaur
-                frame.fill_rectangle(
-                    self.get_opposite(Point { x: (self.dinosaur.x as f32), y: (self.dinosaur.y as f32) }, self.dinosaur.height as f32), 
-                    Size { width: (self.dinosaur.width as f32), height: (self.dinosaur.height as f32) }, 
-                    Color::BLACK
-                );
-
-                // draw the obstacles
-                for obstacle in self.obstacles.iter() {
+                // draw the ghosts still alive (behind the dinosaur)
+                for ghost in self.ghosts.iter().filter(|ghost| !ghost.has_lost) {
                     frame.fill_rectangle(
-                        self.get_opposite(Point { x: (obstacle.x as f32), y: (obstacle.y as f32) }, obstacle.height as f32), 
-                        Size { width: (obstacle.width as f32), height: (obstacle.height as f32) }, 
+                        self.get_opposite(Point { x: (ghost.dinosaur.x as f32), y: (ghost.dinosaur.y as f32) }, ghost.dinosaur.height as f32),
+                        Size { width: (ghost.dinosaur.width as f32), height: (ghost.dinosaur.height as f32) },
+                        GHOST_COLOR
+                    );
+                }
+
+                // draw the dinosaur (see GameView for the sprites)
+                if self.show_hitbox {
+                    frame.fill_rectangle(
+                        self.get_opposite(Point { x: (self.dinosaur.x as f32), y: (self.dinosaur.y as f32) }, self.dinosaur.height as f32), 
+                        Size { width: (self.dinosaur.width as f32), height: (self.dinosaur.height as f32) }, 
                         Color::BLACK
                     );
                 }
 
+                draw_obstacles(frame, &self.params, &self.obstacles, self.show_hitbox);
+
                 // draw the brain
-                match &self.brain {
-                    Some(brain) => {
-                        let action_activate = brain.get_activations(&self.obstacles);
-                        draw_legend(frame, &action_activate);
-                        for neurone_web in &brain.neurone_web {
-                            let action = &neurone_web.action;
-                            let mut last_neuron : Option<&Neurone> = None;
-                            for neurone in &neurone_web.neurones {
-                                let color_action = get_color_from_action(&action);
-                                // draw highlight
-                                let highlight_thickness = 2.0;
-                                frame.fill_rectangle(
-                                    self.get_opposite(Point { x: (neurone.x as f32), y: (neurone.y as f32) }, neurone.height as f32), 
-                                    Size { width: (neurone.width as f32), height: (neurone.height as f32) }, 
-                                    color_action
-                                );
-
-                                let width_without_thick = neurone.width as f32 - 2.0 * highlight_thickness;
-                                let height_without_thick = neurone.height as f32 - 2.0 * highlight_thickness;
-                                frame.fill_rectangle(
-                                    self.get_opposite(
-                                        Point { 
-                                            x: (neurone.x as f32 + highlight_thickness), 
-                                            y: (neurone.y as f32 + highlight_thickness) 
-                                        }, 
-                                        height_without_thick
-                                    ), 
-                                    Size { width: width_without_thick, height: height_without_thick }, 
-                                    get_color_from_neurone(neurone)
-                                );
-
-                                // draw the link
-                                match last_neuron {
-                                    Some(last_neuron) => {
-                                        let last_neuron_point = self.get_opposite(Point { 
-                                            x: (last_neuron.x as f32) + (last_neuron.width as f32)/2.0, 
-                                            y: (last_neuron.y as f32) - (last_neuron.height as f32)/2.0
-                                        }, last_neuron.height as f32);
-                                        let neurone_point = self.get_opposite(Point { 
-                                            x: (neurone.x as f32) + (neurone.width as f32)/2.0, 
-                                            y: (neurone.y as f32) - (neurone.height as f32)/2.0 
-                                        }, last_neuron.height as f32);
-                                        let path = Path::line(
-                                            last_neuron_point, 
-                                            neurone_point
-                                        );
-                                        frame.stroke(
-                                            &path,
-                                            Stroke {
-                                                width: 1.0,
-                                                line_cap: LineCap::Round,
-                                                line_join: LineJoin::Round,
-                                                ..Stroke::default()
-                                                    .with_color(color_action)
-                                                    .clone()
-                                            }
-                                            
-                                        );
-                                    },
-                                    None => {}
-                                }
-                                last_neuron = Some(neurone);
-                            }
-                        }
-                    },
-                    None => {}
+                if let Some(brain) = &self.brain {
+                    let action_activate = brain.get_activations(&self.obstacles, &self.dinosaur, self.get_speed());
+                    draw_legend(frame, &self.params.get_neurone_conditions(), &action_activate);
+                    for neurone_web in &brain.neurone_web {
+                        // the live state of the web (which neurone fire, which one stop the web)
+                        let web_state = neurone_web.get_state(&self.obstacles, &self.dinosaur, self.get_speed());
+                        draw_neurone_web(frame, &self.params, neurone_web, Some(&web_state), Some(&self.dinosaur), web_state.is_activated);
+                    }
                 }
             }
+
+            // draw the head-up display (under the legend of the brain)
+            let mut y = if self.brain.is_some() && !self.has_lost { 180.0 } else { 30.0 };
+            for line in self.get_hud_lines() {
+                frame.fill_text(Text {
+                    content: line,
+                    position: Point { x: 0.0, y },
+                    size: 20.0,
+                    color: Color::BLACK,
+                    ..Text::default()
+                });
+                y += 25.0;
+            }
+
+            // draw the state of the playback
+            frame.fill_text(Text {
+                content: self.playback.get_status(),
+                position: Point { x: 700.0, y: 0.0 },
+                size: 20.0,
+                color: Color::BLACK,
+                ..Text::default()
+            });
+            draw_hitbox_hint(frame);
+            if let Some(replay) = &self.replay {
+                frame.fill_text(Text {
+                    content: format!("Replay {}/{} ([<-] [->] seek)", self.tick, replay.nb_tick),
+                    position: Point { x: 700.0, y: 30.0 },
+                    size: 20.0,
+                    color: Color::BLACK,
+                    ..Text::default()
+                });
+            }
         });
 
         vec![geometry]
     }
 }
 
+impl SpriteScene<Message> for Game {
+    fn get_land(&self) -> &Game {
+        self
+    }
+
+    fn get_sprite_dinosaurs(&self) -> Vec<&Dinosaur> {
+        vec![&self.dinosaur]
+    }
+
+    fn hide_sprites(&self) -> bool {
+        self.show_hitbox || self.has_lost
+    }
+}
+
+/// draw the obstacles without sprite (the hole), or all the hitboxes of the obstacles
+/// NOTE : the sprites are drawn under the canvas by GameView
+pub fn draw_obstacles(frame : &mut Frame, params : &GameParameters, obstacles : &[Obstacle], show_hitbox : bool) {
+    for obstacle in obstacles {
+        if !show_hitbox && SPRITES.get_obstacle_sprite(&obstacle.type_).is_some() {
+            continue;
+        }
+        frame.fill_rectangle(
+            get_opposite(params, Point { x: (obstacle.x as f32), y: (obstacle.y as f32) }, obstacle.height as f32),
+            Size { width: (obstacle.width as f32), height: (obstacle.height as f32) },
+            Color::BLACK
+        );
+    }
+}
+
+/// draw the key to switch between the sprites and the hitboxes (under the state of the playback)
+pub fn draw_hitbox_hint(frame : &mut Frame) {
+    frame.fill_text(Text {
+        content: "[h] hitbox".to_string(),
+        position: Point { x: 700.0, y: 60.0 },
+        size: 20.0,
+        color: Color::BLACK,
+        ..Text::default()
+    });
+}
+
+/// draw a neurone web, with the live state of its neurones if given
+/// NOTE : the neurones anchored to the dinosaur follow the given dinosaur (at their ground position without it) and are marked by a dot
+/// NOTE : the links are drawn thicker if thick_links (an activated or a selected web)
+pub fn draw_neurone_web(frame : &mut Frame, params : &GameParameters, neurone_web : &NeuroneWeb, web_state : Option<&NeuroneWebState>, dinosaur : Option<&Dinosaur>, thick_links : bool) {
+    let color_action = get_color_from_action(&neurone_web.action);
+    let mut last_neuron : Option<(&Neurone, Point)> = None;
+    for (i, neurone) in neurone_web.neurones.iter().enumerate() {
+        let (x, y) = match dinosaur {
+            Some(dinosaur) => neurone.get_position(dinosaur),
+            None => (neurone.x, neurone.y),
+        };
+        let (x, y) = (x as f32, y as f32);
+        // draw the firing state around the neurone
+        let overlay_color = match web_state {
+            Some(web_state) if web_state.veto == Some(i) => Some(VETO_COLOR),
+            Some(web_state) if web_state.met_conditions[i] => Some(FIRING_COLOR),
+            _ => None,
+        };
+        if let Some(overlay_color) = overlay_color {
+            let overlay_thickness = 3.0;
+            frame.fill_rectangle(
+                get_opposite(
+                    params,
+                    Point {
+                        x: (x - overlay_thickness),
+                        y: (y - overlay_thickness)
+                    },
+                    neurone.height as f32 + 2.0 * overlay_thickness
+                ),
+                Size {
+                    width: neurone.width as f32 + 2.0 * overlay_thickness,
+                    height: neurone.height as f32 + 2.0 * overlay_thickness
+                },
+                overlay_color
+            );
+        }
+        // draw highlight
+        let highlight_thickness = 2.0;
+        frame.fill_rectangle(
+            get_opposite(params, Point { x, y }, neurone.height as f32), 
+            Size { width: (neurone.width as f32), height: (neurone.height as f32) }, 
+            color_action
+        );
+
+        let width_without_thick = neurone.width as f32 - 2.0 * highlight_thickness;
+        let height_without_thick = neurone.height as f32 - 2.0 * highlight_thickness;
+        frame.fill_rectangle(
+            get_opposite(
+                params,
+                Point { 
+                    x: (x + highlight_thickness), 
+                    y: (y + highlight_thickness) 
+                }, 
+                height_without_thick
+            ), 
+            Size { width: width_without_thick, height: height_without_thick }, 
+            get_color_from_neurone(neurone)
+        );
+
+        // mark the neurone anchored to the dinosaur
+        if neurone.anchor == NeuroneAnchor::Dinosaur {
+            let dot_size = 4.0;
+            frame.fill_rectangle(
+                get_opposite(
+                    params,
+                    Point {
+                        x: x + (neurone.width as f32 - dot_size) / 2.0,
+                        y: y + (neurone.height as f32 - dot_size) / 2.0
+                    },
+                    dot_size
+                ),
+                Size { width: dot_size, height: dot_size },
+                Color::BLACK
+            );
+        }
 
-fn draw_legend(frame : &mut Frame, action_activated :&HashSet<NeuroneWebAction>) {
+        // draw the link
+        let point = Point { x, y };
+        if let Some((last_neuron, last_point)) = last_neuron {
+            let last_neuron_point = get_opposite(params, Point { 
+                x: last_point.x + (last_neuron.width as f32)/2.0, 
+                y: last_point.y - (last_neuron.height as f32)/2.0
+            }, last_neuron.height as f32);
+            let neurone_point = get_opposite(params, Point { 
+                x: point.x + (neurone.width as f32)/2.0, 
+                y: point.y - (neurone.height as f32)/2.0 
+            }, last_neuron.height as f32);
+            let path = Path::line(
+                last_neuron_point, 
+                neurone_point
+            );
+            frame.stroke(
+                &path,
+                Stroke {
+                    width: if thick_links { 4.0 } else { 1.0 },
+                    line_cap: LineCap::Round,
+                    line_join: LineJoin::Round,
+                    ..Stroke::default()
+                        .with_color(color_action)
+                        .clone()
+                }
+                
+            );
+        }
+        last_neuron = Some((neurone, point));
+    }
+}
+
+/// the color around a neurone with its condition met
+const FIRING_COLOR: Color = Color { r : 1.0, g : 0.8, b : 0.0, a : 1.0 };
+/// the color around the prevent activate neurone that stop its web
+const VETO_COLOR: Color = Color { r : 1.0, g : 0.0, b : 0.0, a : 1.0 };
+
+/// draw the legend of the neurones (one line per condition, activate then prevent activate) and of the actions
+fn draw_legend(frame : &mut Frame, all_conditions : &[NeuroneActivationCondition], action_activated :&HashSet<NeuroneWebAction>) {
     // draw all the possible activation of neurone :
-    let all_conditions = vec![NeuroneActivationCondition::Air, NeuroneActivationCondition::Obstacle];
-    let all_activations = vec![NeuroneActivation::Activate, NeuroneActivation::PreventActivate];
+    let all_activations = [NeuroneActivation::Activate, NeuroneActivation::PreventActivate];
     let all_actions = vec![NeuroneWebAction::Jump, NeuroneWebAction::Bend, NeuroneWebAction::Unbend];
 
 
     let mut y = 0.0;
     for condition in all_conditions {
-        for activation in &all_activations {
-            let color = get_color_from_activation(*activation, condition);
+        for (i, activation) in all_activations.iter().enumerate() {
+            let color = get_color_from_activation(*activation, *condition);
             frame.fill_rectangle(
-                Point { x: 0.0, y: y }, 
+                Point { x: i as f32 * 25.0, y }, 
                 Size { width: 20.0, height: 20.0 }, 
                 color
             );
-            frame.fill_text(Text {
-                content: format!("{} when cross a {}", activation, condition),
-                position: Point { x: 30.0, y: y },
-                size: 20.0,
-                color: Color::BLACK,
-                ..Text::default()
-            });
-
-            y += 30.0;
         }
+        frame.fill_text(Text {
+            content: if condition.is_proprioceptive() { format!("when {}", condition.to_string().to_lowercase()) } else { format!("when cross a {}", condition) },
+            position: Point { x: 55.0, y },
+            size: 20.0,
+            color: Color::BLACK,
+            ..Text::default()
+        });
+
+        y += 30.0;
     }
 
     // action
@@ -519,7 +1115,7 @@ fn draw_legend(frame : &mut Frame, action_activated :&HashSet<NeuroneWebAction>)
     for action in &all_actions {
         let color = get_color_from_action(action);
         frame.fill_rectangle(
-            Point { x: x, y: y }, 
+            Point { x, y }, 
             Size { width: 20.0, height: 20.0 }, 
             color
         );
@@ -535,7 +1131,25 @@ fn draw_legend(frame : &mut Frame, action_activated :&HashSet<NeuroneWebAction>)
 
         frame.fill_text(Text {
             content: format!("{} action", action),
-            position: Point { x: x + 30.0, y: y },
+            position: Point { x: x + 30.0, y },
+            size: 20.0,
+            color: Color::BLACK,
+            ..Text::default()
+        });
+
+        y += 30.0;
+    }
+
+    // the live state of the neurones
+    for (color, content) in [(FIRING_COLOR, "condition met"), (VETO_COLOR, "stop its web (thick link : web activated)")] {
+        frame.fill_rectangle(
+            Point { x, y }, 
+            Size { width: 20.0, height: 20.0 }, 
+            color
+        );
+        frame.fill_text(Text {
+            content: content.to_string(),
+            position: Point { x: x + 30.0, y },
             size: 20.0,
             color: Color::BLACK,
             ..Text::default()
@@ -551,12 +1165,14 @@ fn draw_legend(frame : &mut Frame, action_activated :&HashSet<NeuroneWebAction>)
 mod tests {
     use rand::Rng;
 
+    use crate::utils::get_test_path;
+
     use super::*;
 
     #[test]
     fn test_random_coherence() {
         // test the random number generator and the seed "test"
-        let mut game = Game::new(&GameParameters::new_default(), Instant::now(), "test", None, None);
+        let mut game = Game::new(&GameParameters::new_default(), "test", None, None);
         let random_number0: u32 = game.land_rng.gen();
         let random_number1: u32 = game.land_rng.gen(); 
         let random_number2: u32 = game.land_rng.gen();
@@ -569,4 +1185,164 @@ mod tests {
         assert_eq!(random_number3, 2036600936);
         assert_eq!(random_number4, 640383128);
     }
+
+    #[test]
+    fn test_step_is_deterministic() {
+        // two games with the same seed must be in the exact same state after the same number of tick
+        let params = GameParameters::new_default();
+        let mut game0 = Game::new(&params, "test", None, None);
+        let mut game1 = Game::new(&params, "test", None, None);
+        for _ in 0..200 {
+            game0.step();
+            game1.step();
+        }
+
+        assert_eq!(game0.tick, 200);
+        assert_eq!(game0.last_time_update, game0.get_tick_time(200));
+        assert_eq!(game0.dinosaur, game1.dinosaur);
+        assert_eq!(game0.obstacles, game1.obstacles);
+
+        // a lost game doesn't move anymore
+        while !game0.has_lost {
+            game0.step();
+            game1.step();
+        }
+        let tick = game0.tick;
+        game0.step();
+        assert_eq!(game0.tick, tick);
+        assert!(game1.has_lost);
+        assert_eq!(game1.tick, tick);
+        assert_eq!(game0.dinosaur, game1.dinosaur);
+        assert_eq!(game0.obstacles, game1.obstacles);
+        assert_eq!(game0.score, game1.score);
+    }
+
+    #[test]
+    fn test_game_speed() {
+        let params = GameParameters::new_default();
+        let mut game = Game::new(&params, "speed", None, None);
+        assert_eq!(game.get_speed(), 0.0);
+        game.score = 1000;
+        assert_eq!(game.get_speed(), 1.0);
+    }
+
+    #[test]
+    fn test_playback_speed() {
+        let (mut game, _) = <Game as Application>::new(CustomFlags::Play(GameParameters::new_default(), None));
+        // 4 updates at a quarter speed is one tick
+        let _ = Application::update(&mut game, Message::ChangeSpeed(0.25));
+        for _ in 0..4 {
+            let _ = Application::update(&mut game, Message::Update);
+        }
+        assert_eq!(game.tick, 1);
+
+        // the speed is clamped
+        for _ in 0..10 {
+            let _ = Application::update(&mut game, Message::ChangeSpeed(2.0));
+        }
+        let _ = Application::update(&mut game, Message::Update);
+        assert_eq!(game.tick, 9);
+
+        // nothing move in pause, except with a step
+        let _ = Application::update(&mut game, Message::TogglePause);
+        let _ = Application::update(&mut game, Message::Update);
+        assert_eq!(game.tick, 9);
+        let _ = Application::update(&mut game, Message::Step);
+        assert_eq!(game.tick, 10);
+
+        // the restart keep the playback
+        let _ = Application::update(&mut game, Message::Restart);
+        assert_eq!(game.tick, 0);
+        assert!(game.playback.paused);
+        assert_eq!(game.playback.speed, MAX_SPEED);
+    }
+
+    #[test]
+    fn test_high_scores() {
+        // the folder of the file is created on save
+        let folder = get_test_path("high_scores/");
+        let path = format!("{}high_scores.json", folder);
+        let _ = std::fs::remove_dir_all(&folder);
+        // no file is no high score
+        let mut high_scores = HighScores::new_from_file(&path);
+        assert_eq!(high_scores, HighScores::default());
+
+        assert!(high_scores.update(false, 12));
+        assert!(!high_scores.update(false, 5));
+        assert!(high_scores.update(true, 3));
+        high_scores.save(&path).unwrap();
+        let loaded = HighScores::new_from_file(&path);
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(loaded.get(false), 12);
+        assert_eq!(loaded.get(true), 3);
+    }
+
+    #[test]
+    fn test_hud_lines() {
+        let params = GameParameters::new_default();
+        let mut game = Game::new(&params, "test", None, None);
+        for _ in 0..params.game_fps * 2 {
+            game.step();
+        }
+        let lines = game.get_hud_lines();
+        assert_eq!(lines[0], format!("Score : {}", game.score));
+        assert_eq!(lines[1], "Time : 2.0 s");
+        assert_eq!(lines[3], "Land seed : test");
+        // no brain and no high score in the headless games
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_brain_selection() {
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brains : Vec<Brain> = (0..3).map(|_| Brain::new(&params, &mut rng)).collect();
+
+        let mut selection = BrainSelection::new(brains.clone(), 2, true);
+        assert_eq!(selection.get_ghost_brains(), vec![brains[0].clone(), brains[1].clone()]);
+        selection.cycle(1);
+        assert_eq!(selection.get_brain(), &brains[0]);
+        selection.cycle(-1);
+        assert_eq!(selection.get_brain(), &brains[2]);
+
+        // the ghosts play on the same land, and stop with the game
+        let (mut game, _) = <Game as Application>::new(CustomFlags::Brain(selection, params.clone(), None));
+        assert_eq!(game.ghosts.len(), 2);
+        while !game.has_lost {
+            let _ = Application::update(&mut game, Message::Update);
+            for ghost in game.ghosts.iter().filter(|ghost| !ghost.has_lost) {
+                assert_eq!(ghost.obstacles, game.obstacles);
+            }
+        }
+        assert!(game.ghosts.iter().all(|ghost| ghost.tick <= game.tick));
+
+        // the next brain restart the game (the selected brain isn't a ghost)
+        let _ = Application::update(&mut game, Message::ChangeBrain(1));
+        assert_eq!(game.tick, 0);
+        assert_eq!(game.brain.as_ref(), Some(&brains[0]));
+        assert_eq!(game.ghosts.iter().map(|ghost| ghost.brain.clone().unwrap()).collect::<Vec<Brain>>(), vec![brains[1].clone(), brains[2].clone()]);
+        assert!(game.get_hud_lines().contains(&"Brain : 1/3 ([b] next)".to_string()));
+    }
+
+    #[test]
+    fn test_serialize_game() {
+        // a serialized game must continue exactly like the original one
+        let params = GameParameters::new_default();
+        let mut game = Game::new(&params, "test", None, None);
+        for _ in 0..100 {
+            game.step();
+        }
+
+        let mut copy : Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        while !game.has_lost {
+            game.step();
+            copy.step();
+        }
+
+        assert!(copy.has_lost);
+        assert_eq!(game.tick, copy.tick);
+        assert_eq!(game.dinosaur, copy.dinosaur);
+        assert_eq!(game.obstacles, copy.obstacles);
+        assert_eq!(game.score, copy.score);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,178 @@
+use std::path::Path;
+
+use crate::brain::{Brain, IntermediateResult};
+use crate::controller::Controller;
+use crate::error::{Error, Result};
+use crate::evaluate::{load_any_brains, LoadedBrains};
+use crate::network::NeuralNetwork;
+use crate::params::{GameParameters, ControllerType};
+use crate::utils::get_brain_indexes;
+
+/// the summary of a saved generation (one brain file)
+#[derive(Debug, Clone)]
+pub struct GenerationSummary {
+    pub generation : u64,
+    pub score : f64,
+    /// the energie of the champion
+    pub energy : f64,
+    /// the number of brains saved for this generation
+    pub nb_brain : usize,
+    /// the size of the champion : the neurone webs (or the layers of a network) and the neurones (see Controller::get_size)
+    pub nb_neurone_web : usize,
+    pub nb_neurone : usize,
+}
+
+/// load the summary of every valid brain file of the training folder (of brains or other controllers), sorted by generation
+/// NOTE : the invalid brain files are skipped (they can be written by a running training)
+pub fn load_summaries<C : Controller>(folder_path : &str, params : &GameParameters) -> Vec<GenerationSummary> {
+    get_brain_indexes(folder_path)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|i| {
+            let result = IntermediateResult::<C>::new_from_file(&format!("{}brain{}.json", folder_path, i)).ok()?;
+            let champion = &result.brains[0];
+            let (nb_neurone_web, nb_neurone) = champion.get_size();
+            Some(GenerationSummary {
+                generation : i,
+                score : result.score,
+                energy : champion.get_energie(params),
+                nb_brain : result.brains.len(),
+                nb_neurone_web,
+                nb_neurone,
+            })
+        })
+        .collect()
+}
+
+/// get the lines describing a brain (energy, then its webs and their neurones)
+pub fn get_brain_lines(params : &GameParameters, brain : &Brain) -> Vec<String> {
+    let mut lines = vec![format!(
+        "energy {:.0}, {} neurone webs, {} neurones",
+        brain.get_energie(params), brain.neurone_web.len(), brain.get_nb_neurone()
+    )];
+    for (i, web) in brain.neurone_web.iter().enumerate() {
+        lines.push(format!("    web {} : {} ({} neurones)", i, web.action, web.neurones.len()));
+        for neurone in &web.neurones {
+            lines.push(format!(
+                "        ({:.0}, {:.0}) {}x{} on the {} : {} -> {}",
+                neurone.x, neurone.y, neurone.width, neurone.height, neurone.anchor, neurone.activation_condition, neurone.activation
+            ));
+        }
+    }
+    lines
+}
+
+/// get the lines describing a neural network (energy, then its layers)
+pub fn get_network_lines(params : &GameParameters, network : &NeuralNetwork) -> Vec<String> {
+    let mut sizes = vec![network.layers.first().and_then(|layer| layer.weights.first()).map_or(0, Vec::len)];
+    sizes.extend(network.layers.iter().map(|layer| layer.biases.len()));
+    let actions : Vec<String> = network.actions.iter().map(|action| action.to_string()).collect();
+    vec![
+        format!("energy {:.0}, neural network of {} weights", network.get_energie(params), network.get_nb_parameter()),
+        format!(
+            "    layers : {} (sees {} obstacles, outputs {})",
+            sizes.iter().map(|size| size.to_string()).collect::<Vec<String>>().join(" -> "), network.nb_obstacle, actions.join(", ")
+        ),
+    ]
+}
+
+/// print the generations of a training folder
+fn inspect_folder(mut folder_path : String) -> Result<()> {
+    if !folder_path.ends_with('/') {
+        folder_path = format!("{}/", folder_path);
+    }
+    let params_path = format!("{}params.json", folder_path);
+    if !Path::new(&params_path).exists() {
+        return Err(Error::MissingRunFolder { path : folder_path });
+    }
+    let params = GameParameters::new_from_file(&params_path)?;
+
+    let (summaries, size_name) = match params.controller {
+        ControllerType::NeuroneWeb => (load_summaries::<Brain>(&folder_path, &params), "webs"),
+        ControllerType::NeuralNetwork => (load_summaries::<NeuralNetwork>(&folder_path, &params), "layers"),
+    };
+    println!("training folder {} : {} saved generations, land seed {}", folder_path, summaries.len(), params.land_seed);
+    println!("    {:>10} {:>10} {:>12} {:>8} {:>6} {:>9}", "generation", "score", "energy", "brains", size_name, "neurones");
+    for summary in summaries {
+        println!(
+            "    {:>10} {:>10.1} {:>12.0} {:>8} {:>6} {:>9}",
+            summary.generation, summary.score, summary.energy, summary.nb_brain, summary.nb_neurone_web, summary.nb_neurone
+        );
+    }
+    Ok(())
+}
+
+/// print the content of a brain file, or the generations of a training folder
+pub fn inspect_pipeline(path : &str, params : &GameParameters) -> Result<()> {
+    if Path::new(path).is_dir() {
+        return inspect_folder(path.to_string());
+    }
+
+    // the brain file can hold neurone webs or neural networks
+    let lines : Vec<Vec<String>> = match load_any_brains(path, params)? {
+        LoadedBrains::NeuroneWeb(brains) => brains.iter().map(|brain| get_brain_lines(params, brain)).collect(),
+        LoadedBrains::NeuralNetwork(networks) => networks.iter().map(|network| get_network_lines(params, network)).collect(),
+    };
+    println!("brain file {} : {} brains", path, lines.len());
+    for (i, brain_lines) in lines.iter().enumerate() {
+        println!("brain {} : {}", i, brain_lines.join("\n"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::utils::{str_to_u8_array, write_json, get_test_path};
+
+    use super::*;
+
+    #[test]
+    fn test_load_summaries() {
+        let params = GameParameters::new_default();
+        let folder_path = get_test_path("inspect/");
+        let _ = fs::remove_dir_all(&folder_path);
+        fs::create_dir(&folder_path).unwrap();
+
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        for (i, score) in [(20, 7.0), (3, 2.0)] {
+            let result = IntermediateResult {
+                brains : vec![Brain::new(&params, &mut rng), Brain::new(&params, &mut rng)],
+                rng : rng.clone(),
+                score,
+                seeds : vec![params.land_seed.clone()],
+                scores : vec![vec![score as u64]],
+                parents : Vec::new(),
+                speciation : None,
+                species_parents : Vec::new(),
+            };
+            write_json(&format!("{}brain{}.json", folder_path, i), &result).unwrap();
+        }
+        // a brain file being written is skipped
+        fs::write(format!("{}brain40.json", folder_path), "{\"brains\" : [").unwrap();
+
+        let summaries = load_summaries::<Brain>(&folder_path, &params);
+        assert_eq!(summaries.iter().map(|summary| summary.generation).collect::<Vec<u64>>(), vec![3, 20]);
+        assert_eq!(summaries[0].score, 2.0);
+        assert_eq!(summaries[1].score, 7.0);
+        assert_eq!(summaries[1].nb_brain, 2);
+        let champion = &IntermediateResult::<Brain>::new_from_file(&format!("{}brain20.json", folder_path)).unwrap().brains[0];
+        assert_eq!(summaries[1].energy, champion.get_energie(&params));
+        assert_eq!(summaries[1].nb_neurone, champion.get_nb_neurone());
+
+        // the brain files of neural networks are read as networks
+        let network = NeuralNetwork::new(&params, &mut rng);
+        let result = IntermediateResult { brains : vec![network.clone()], rng : rng.clone(), score : 9.0, seeds : Vec::new(), scores : Vec::new(), parents : Vec::new(), speciation : None, species_parents : Vec::new() };
+        write_json(&format!("{}brain20.json", folder_path), &result).unwrap();
+        assert!(load_summaries::<Brain>(&folder_path, &params).iter().all(|summary| summary.generation != 20));
+        let summaries = load_summaries::<NeuralNetwork>(&folder_path, &params);
+        assert_eq!(summaries.iter().map(|summary| summary.generation).collect::<Vec<u64>>(), vec![20]);
+        assert_eq!((summaries[0].nb_neurone_web, summaries[0].nb_neurone), network.get_size());
+
+        fs::remove_dir_all(&folder_path).unwrap();
+    }
+}
@@ -1,10 +1,18 @@
+use std::path::Path;
 
-use std::{fs::File, io::BufReader};
-
-use brain::{brain_train_pipeline, IntermediateResult};
-use game::{Game, CustomFlags};
+use brain::brain_train_pipeline;
+use controller::Controller;
+use dashboard::Dashboard;
+use editor::Editor;
+use error::Error;
+use evaluate::LoadedBrains;
+use race::RaceView;
+use replay::Replay;
+use error::Result;
+use game::{Game, CustomFlags, BrainSelection};
 use iced::{Settings, Application, window};
-use program_args::ProgramArgs;
+use params::{GameParameters, TrainingConfig, ControllerType};
+use program_args::{ProgramArgs, Command, ParamsCommand, GameOptions};
 use structopt::StructOpt;
 
 
@@ -14,67 +22,207 @@ mod utils;
 mod params;
 mod neurone;
 mod brain;
+mod selection;
+mod stats;
+mod error;
 mod program_args;
+mod dashboard;
+mod replay;
+mod editor;
+mod sprite;
+mod evaluate;
+mod race;
+mod inspect;
+mod controller;
+mod network;
+mod species;
 
 
-fn main() -> iced::Result {
-    // get var env to say if we want to run the game or the brain train
-    let args = ProgramArgs::from_args();
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error : {}", error);
+        std::process::exit(error.exit_code());
+    }
+}
 
-    // if we want to play the game
-    if args.play {
-        let params;
-        if args.params_path.is_some() {
-            params = params::GameParameters::new_from_file(args.params_path.unwrap().as_str());
-        }else{
-            params = params::GameParameters::new_default();
-        }
-        // run the game
-        Game::run(Settings {
-            antialiasing: true,
-            flags : CustomFlags::Play(params.clone()),
-            window: window::Settings {
-                position: window::Position::Centered,
-                size: (params.game_width as u32, params.game_height as u32),
-                ..window::Settings::default()
-            },
-            ..Settings::default()
-        })
-    }else if args.brain_path.is_some() {
-        // run the brain play
-        let params;
-        if args.params_path.is_some() {
-            params = params::GameParameters::new_from_file(args.params_path.unwrap().as_str());
-        }else{
-            params = params::GameParameters::new_default();
-        }
+fn run() -> Result<()> {
+    // the subcommand say what we want to run (the help is printed without subcommand)
+    match ProgramArgs::from_args().command {
+        Command::Play { game, record_path } => run_play(&game, record_path),
+        Command::Watch { brain_path, brain_index, ghosts, game, record_path } => run_watch(brain_path, brain_index, ghosts, &game, record_path),
+        Command::Train { folder_path, params_path, training_path, nb_worker } => brain_train_pipeline(folder_path, params_path, training_path, nb_worker),
+        Command::Evaluate { brain_path, seeds, seed_range, max_score, max_tick, json, game } => {
+            evaluate::evaluate_pipeline(&brain_path, &game.get_params()?, &seeds, seed_range.as_deref(), max_score, max_tick, json)
+        },
+        Command::Inspect { path, game } => inspect::inspect_pipeline(&path, &game.get_params()?),
+        Command::Race { brain_paths, game } => run_race(&brain_paths, &game),
+        Command::Replay { replay_path } => run_replay(&replay_path),
+        Command::Dashboard { folder_path } => run_dashboard(folder_path),
+        Command::Edit { brain_path, brain_index, game } => run_edit(brain_path, brain_index, &game),
+        Command::Params(ParamsCommand::Init { params_path, training_path, force }) => run_params_init(&params_path, training_path, force),
+    }
+}
+
+/// the settings of a centered window of the given size
+fn get_window_settings(size : (u32, u32)) -> window::Settings {
+    window::Settings {
+        position: window::Position::Centered,
+        size,
+        ..window::Settings::default()
+    }
+}
+
+/// let the user play the game
+fn run_play(game : &GameOptions, record_path : Option<String>) -> Result<()> {
+    let params = game.get_params()?;
+    Game::run(Settings {
+        antialiasing: true,
+        window: get_window_settings((params.game_width as u32, params.game_height as u32)),
+        flags : CustomFlags::Play(params, record_path),
+        ..Settings::default()
+    })?;
+    Ok(())
+}
 
+/// let a brain of the brain file play the game (the others can be played in turn or as ghosts)
+/// NOTE : the neural networks are played in a race (the game only draws the neurone webs), with the other networks as ghosts
+fn run_watch(brain_path : String, brain_index : usize, ghosts : bool, game : &GameOptions, record_path : Option<String>) -> Result<()> {
+    let params = game.get_params()?;
+    let size = (params.game_width as u32, params.game_height as u32);
+    match evaluate::load_any_brains(&brain_path, &params)? {
+        LoadedBrains::NeuroneWeb(brains) => {
+            print_brains(&brain_path, brain_index, &brains, &params)?;
+            let selection = BrainSelection::new(brains, brain_index, ghosts);
+            Game::run(Settings {
+                antialiasing: true,
+                window: get_window_settings(size),
+                flags : CustomFlags::Brain(selection, params, record_path),
+                ..Settings::default()
+            })?;
+        },
+        LoadedBrains::NeuralNetwork(networks) => {
+            print_brains(&brain_path, brain_index, &networks, &params)?;
+            if record_path.is_some() {
+                return Err(Error::NotRecordable { path : brain_path });
+            }
+            let (networks, names) = race::get_watched_racers(&networks, brain_index, ghosts);
+            RaceView::run(Settings {
+                antialiasing: true,
+                window: get_window_settings(size),
+                ..Settings::with_flags((params, networks, names))
+            })?;
+        },
+    }
+    Ok(())
+}
 
-        let file = File::open(args.brain_path.unwrap()).expect("Unable to open file");
-        let reader = BufReader::new(file);
+/// print the energy of the brains of the brain file, after checking that it has the brain to watch
+fn print_brains<C : Controller>(brain_path : &str, brain_index : usize, brains : &[C], params : &GameParameters) -> Result<()> {
+    if brain_index >= brains.len() {
+        return Err(Error::BrainIndexOutOfRange { path : brain_path.to_string(), index : brain_index, nb_brain : brains.len() });
+    }
+    for (i, brain) in brains.iter().enumerate() {
+        println!("brain {} : energy {:.0}", i, brain.get_energie(params));
+    }
+    Ok(())
+}
 
-        let inter : IntermediateResult = serde_json::from_reader(reader).expect("Unable to read file");
-        if inter.brains.len() == 0 {
-            println!("The brain file is empty");
-            return Ok(());
+/// race all the brains of the files
+/// NOTE : the files must hold the same controller as the first one (neurone webs or neural networks)
+fn run_race(brain_paths : &[String], game : &GameOptions) -> Result<()> {
+    let params = game.get_params()?;
+    match evaluate::load_any_brains(&brain_paths[0], &params)? {
+        LoadedBrains::NeuroneWeb(brains) => run_race_view(params, brains, &brain_paths[1..]),
+        LoadedBrains::NeuralNetwork(networks) => run_race_view(params, networks, &brain_paths[1..]),
+    }
+}
+
+/// race the brains with the brains of the other files
+fn run_race_view<C : Controller + 'static>(params : GameParameters, mut brains : Vec<C>, other_paths : &[String]) -> Result<()> {
+    for brain_path in other_paths {
+        brains.extend(evaluate::load_brains::<C>(brain_path)?);
+    }
+    let names = race::get_brain_names(brains.len());
+    RaceView::run(Settings {
+        antialiasing: true,
+        window: get_window_settings((params.game_width as u32, params.game_height as u32)),
+        ..Settings::with_flags((params, brains, names))
+    })?;
+    Ok(())
+}
+
+/// play again the replay
+fn run_replay(replay_path : &str) -> Result<()> {
+    let replay = Replay::new_from_file(replay_path)?;
+    let size = (replay.params.game_width as u32, replay.params.game_height as u32);
+    Game::run(Settings {
+        antialiasing: true,
+        flags : CustomFlags::Replay(Box::new(replay)),
+        window: get_window_settings(size),
+        ..Settings::default()
+    })?;
+    Ok(())
+}
+
+/// show the dashboard of the training folder
+fn run_dashboard(mut folder_path : String) -> Result<()> {
+    if !folder_path.ends_with('/') {
+        folder_path = format!("{}/", folder_path);
+    }
+    let params_path = format!("{}params.json", folder_path);
+    if !Path::new(&params_path).exists() {
+        return Err(Error::MissingRunFolder { path : folder_path });
+    }
+    let params = GameParameters::new_from_file(&params_path)?;
+    // the game of a champion is shown under a back button
+    let size = (params.game_width.max(1000) as u32, params.game_height.max(650) as u32 + 40);
+    Dashboard::run(Settings {
+        antialiasing: true,
+        window: get_window_settings(size),
+        ..Settings::with_flags((folder_path, params))
+    })?;
+    Ok(())
+}
+
+/// edit the brain by hand
+fn run_edit(brain_path : String, brain_index : usize, game : &GameOptions) -> Result<()> {
+    let params = game.get_params()?;
+    if params.controller == ControllerType::NeuralNetwork {
+        return Err(Error::NotEditable { path : brain_path });
+    }
+    let result = if Path::new(&brain_path).exists() {
+        editor::load_brain_file(&brain_path)?
+    }else{
+        editor::new_brain_file(&params)
+    };
+    if brain_index >= result.brains.len() {
+        return Err(Error::BrainIndexOutOfRange { path : brain_path, index : brain_index, nb_brain : result.brains.len() });
+    }
+    let size = editor::get_window_size(&params);
+    Editor::run(Settings {
+        antialiasing: true,
+        window: get_window_settings(size),
+        ..Settings::with_flags((params, result, brain_index, brain_path))
+    })?;
+    Ok(())
+}
+
+/// write the default params (and training config) to edit them
+/// NOTE : an existing file is not replaced without force
+fn run_params_init(params_path : &str, training_path : Option<String>, force : bool) -> Result<()> {
+    let write_pretty_json = |path : &str, json : serde_json::Result<String>| -> Result<()> {
+        if !force && Path::new(path).exists() {
+            return Err(Error::FileExists { path : path.to_string() });
         }
-        Game::run(Settings {
-            antialiasing: true,
-            flags : CustomFlags::Brain(inter.brains[0].clone(), params.clone()),
-            window: window::Settings {
-                position: window::Position::Centered,
-                size: (params.game_width as u32, params.game_height as u32),
-                ..window::Settings::default()
-            },
-            ..Settings::default()
-        })
-    }else if args.folder_path.is_some() {
-        // run the brain train
-        brain_train_pipeline(args.folder_path);
-        return Ok(());
-    }else {
-        // error, we need to have at least one argument
-        println!("You need to give at least one argument, run -h to see the help");
-        return Ok(());
+        let json = json.map_err(|source| Error::Write { path : path.to_string(), source : source.into() })?;
+        std::fs::write(path, json).map_err(|source| Error::Write { path : path.to_string(), source })?;
+        println!("{} written", path);
+        Ok(())
+    };
+
+    write_pretty_json(params_path, serde_json::to_string_pretty(&GameParameters::new_default()))?;
+    if let Some(training_path) = training_path {
+        write_pretty_json(&training_path, serde_json::to_string_pretty(&TrainingConfig::new_default()))?;
     }
-}
\ No newline at end of file
+    Ok(())
+}
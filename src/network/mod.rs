@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use rand_pcg::Pcg64;
+use serde::{Serialize, Deserialize};
+
+use crate::controller::Controller;
+use crate::entity::{Dinosaur, Obstacle, ObstacleEntityType};
+use crate::neurone::NeuroneWebAction;
+use crate::params::GameParameters;
+
+/// the scale of the distances given to the network (px)
+const DISTANCE_SCALE: f64 = 1000.0;
+/// the scale of the sizes and of the height of the dinosaur given to the network (px)
+const SIZE_SCALE: f64 = 100.0;
+/// the scale of the velocities given to the network (px/s)
+const VELOCITY_SCALE: f64 = 1000.0;
+/// the number of inputs for each obstacle seen (distance, y, width, height and its type)
+const NB_INPUT_PER_OBSTACLE: usize = 7;
+/// the number of inputs on the state of the game (obstacle speed, game speed, y, velocity and bending of the dinosaur)
+const NB_INPUT_STATE: usize = 5;
+
+/// a fully connected layer of the network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    /// the weights of each neurone of the layer (one row per neurone, one weight per input)
+    pub weights : Vec<Vec<f64>>,
+    pub biases : Vec<f64>,
+}
+
+impl Layer {
+    /// create a layer with random weights (gaussian, scaled by the number of inputs)
+    fn new_random(nb_input : usize, nb_output : usize, rng : &mut Pcg64) -> Self {
+        let normal = Normal::new(0.0, 1.0 / (nb_input.max(1) as f64).sqrt()).expect("the std of the weights is positive");
+        Self {
+            weights : (0..nb_output).map(|_| (0..nb_input).map(|_| normal.sample(rng)).collect()).collect(),
+            biases : (0..nb_output).map(|_| normal.sample(rng)).collect(),
+        }
+    }
+
+    /// get the outputs of the layer (before the activation function)
+    fn forward(&self, inputs : &[f64]) -> Vec<f64> {
+        self.weights.iter()
+            .zip(&self.biases)
+            .map(|(weights, bias)| weights.iter().zip(inputs).map(|(weight, input)| weight * input).sum::<f64>() + bias)
+            .collect()
+    }
+}
+
+/// a small feed-forward neural network (multi layer perceptron) that plays the dinosaur
+/// NOTE : it sees the next obstacles (distance, position, size and type), the speeds and the state of the dinosaur,
+/// each output above 0 does its action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NeuralNetwork {
+    /// the number of obstacles seen in front of the dinosaur
+    pub nb_obstacle : usize,
+    /// the action of each output
+    pub actions : Vec<NeuroneWebAction>,
+    /// the hidden layers (tanh) then the output layer (linear)
+    pub layers : Vec<Layer>,
+}
+
+impl NeuralNetwork {
+    /// create a network with random weights, with the layers of the params and one output per command
+    pub fn new(params : &GameParameters, rng : &mut Pcg64) -> Self {
+        let nb_input = params.network_nb_obstacle * NB_INPUT_PER_OBSTACLE + NB_INPUT_STATE;
+        let mut sizes = vec![nb_input];
+        sizes.extend(&params.network_hidden_layers);
+        sizes.push(params.commands.len());
+
+        Self {
+            nb_obstacle : params.network_nb_obstacle,
+            actions : params.commands.clone(),
+            layers : sizes.windows(2).map(|size| Layer::new_random(size[0], size[1], rng)).collect(),
+        }
+    }
+
+    /// get the inputs of the network (normalized) in this state of the game
+    /// NOTE : the obstacles behind the dinosaur are ignored, a missing obstacle is seen far away with a null size
+    pub fn get_inputs(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> Vec<f64> {
+        let mut ahead : Vec<&Obstacle> = obstacles.iter()
+            .filter(|obstacle| obstacle.x + obstacle.width as f64 >= dinosaur.x)
+            .collect();
+        ahead.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+        let mut inputs = Vec::with_capacity(self.nb_obstacle * NB_INPUT_PER_OBSTACLE + NB_INPUT_STATE);
+        for i in 0..self.nb_obstacle {
+            match ahead.get(i) {
+                Some(obstacle) => inputs.extend([
+                    (obstacle.x - dinosaur.x - dinosaur.width as f64) / DISTANCE_SCALE,
+                    obstacle.y / SIZE_SCALE,
+                    obstacle.width as f64 / SIZE_SCALE,
+                    obstacle.height as f64 / SIZE_SCALE,
+                    matches!(obstacle.type_, ObstacleEntityType::Cactus | ObstacleEntityType::Rock) as u8 as f64,
+                    matches!(obstacle.type_, ObstacleEntityType::Pterodactyle | ObstacleEntityType::PterodactyleWithRock) as u8 as f64,
+                    (obstacle.type_ == ObstacleEntityType::Hole) as u8 as f64,
+                ]),
+                None => inputs.extend([1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            }
+        }
+        inputs.extend([
+            ahead.first().map_or(0.0, |obstacle| obstacle.velocity / VELOCITY_SCALE),
+            speed,
+            dinosaur.y / SIZE_SCALE,
+            dinosaur.velocity / VELOCITY_SCALE,
+            dinosaur.is_bending as u8 as f64,
+        ]);
+
+        inputs
+    }
+
+    /// get the outputs of the network for the inputs
+    pub fn forward(&self, inputs : &[f64]) -> Vec<f64> {
+        let mut values = inputs.to_vec();
+        for (i, layer) in self.layers.iter().enumerate() {
+            values = layer.forward(&values);
+            // the output layer stays linear
+            if i + 1 < self.layers.len() {
+                values.iter_mut().for_each(|value| *value = value.tanh());
+            }
+        }
+
+        values
+    }
+
+    /// get the number of weights and biases of the network
+    pub fn get_nb_parameter(&self) -> usize {
+        self.layers.iter().map(|layer| layer.biases.len() + layer.weights.iter().map(Vec::len).sum::<usize>()).sum()
+    }
+}
+
+impl Controller for NeuralNetwork {
+    fn new_random(params : &GameParameters, rng : &mut Pcg64) -> Self {
+        NeuralNetwork::new(params, rng)
+    }
+
+    /// add a gaussian noise to each weight and bias with the mutation rate
+    fn mutate(&self, params : &GameParameters, rng : &mut Pcg64) -> Self {
+        // NOTE : the std is checked when the params are loaded (see GameParameters::validate)
+        let normal = Normal::new(0.0, params.network_weight_mutation_std).expect("the std of the mutation is positive");
+        let mut network = self.clone();
+        for layer in &mut network.layers {
+            for value in layer.weights.iter_mut().flatten().chain(layer.biases.iter_mut()) {
+                if rng.gen_bool(params.network_weight_mutation_rate) {
+                    *value += normal.sample(rng);
+                }
+            }
+        }
+
+        network
+    }
+
+    /// each neurone (its weights and its bias) is taken from one of the parents
+    /// NOTE : the networks must have the same shape (same params), else the first parent is kept
+    fn crossover(&self, other : &Self, _params : &GameParameters, rng : &mut Pcg64) -> Self {
+        let same_shape = self.layers.len() == other.layers.len() && self.layers.iter().zip(&other.layers)
+            .all(|(a, b)| a.biases.len() == b.biases.len() && a.weights.first().map(Vec::len) == b.weights.first().map(Vec::len));
+        if !same_shape {
+            return self.clone();
+        }
+
+        let mut network = self.clone();
+        for (layer, other_layer) in network.layers.iter_mut().zip(&other.layers) {
+            for i in 0..layer.biases.len() {
+                if rng.gen_bool(0.5) {
+                    layer.weights[i] = other_layer.weights[i].clone();
+                    layer.biases[i] = other_layer.biases[i];
+                }
+            }
+        }
+
+        network
+    }
+
+    fn get_activations(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> HashSet<NeuroneWebAction> {
+        self.forward(&self.get_inputs(obstacles, dinosaur, speed))
+            .into_iter()
+            .zip(&self.actions)
+            .filter(|(output, _)| *output > 0.0)
+            .map(|(_, action)| action.clone())
+            .collect()
+    }
+
+    /// the sum of the absolute weights (the smallest network wins the ties)
+    fn get_energie(&self, params : &GameParameters) -> f64 {
+        let total : f64 = self.layers.iter()
+            .map(|layer| layer.weights.iter().flatten().chain(&layer.biases).map(|value| value.abs()).sum::<f64>())
+            .sum();
+        total * params.network_weight_cost_mult as f64
+    }
+
+    /// the number of layers and of neurones (hidden and output)
+    fn get_size(&self) -> (usize, usize) {
+        (self.layers.len(), self.layers.iter().map(|layer| layer.biases.len()).sum())
+    }
+
+    /// the mean absolute difference of the weights and biases (the networks of different shapes are in different species)
+    fn get_distance(&self, other : &Self, params : &GameParameters) -> f64 {
+        let values = |network : &NeuralNetwork| -> Vec<f64> {
+            network.layers.iter().flat_map(|layer| layer.weights.iter().flatten().chain(&layer.biases)).copied().collect()
+        };
+        let (values, other_values) = (values(self), values(other));
+        if values.len() != other_values.len() || self.get_size() != other.get_size() {
+            return f64::INFINITY;
+        }
+        if values.is_empty() {
+            return 0.0;
+        }
+        let total : f64 = values.iter().zip(&other_values).map(|(value, other_value)| (value - other_value).abs()).sum();
+        total / values.len() as f64 * params.speciation_weight_coefficient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::race::Race;
+    use crate::utils::str_to_u8_array;
+
+    use super::*;
+
+    #[test]
+    fn test_network_shape() {
+        let mut params = GameParameters::new_default();
+        params.network_hidden_layers = vec![6, 4];
+        params.commands = vec![NeuroneWebAction::Jump, NeuroneWebAction::Bend];
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let network = NeuralNetwork::new(&params, &mut rng);
+        let nb_input = params.network_nb_obstacle * NB_INPUT_PER_OBSTACLE + NB_INPUT_STATE;
+        assert_eq!(network.get_size(), (3, 6 + 4 + 2));
+        assert_eq!(network.get_nb_parameter(), (nb_input + 1) * 6 + 7 * 4 + 5 * 2);
+
+        // no obstacle : the first obstacle is seen far away
+        let dinosaur = Dinosaur::new_dinosaur(&params, 0.0);
+        let inputs = network.get_inputs(&[], &dinosaur, 0.0);
+        assert_eq!(inputs.len(), nb_input);
+        assert_eq!(inputs[0], 1.0);
+        assert_eq!(network.forward(&inputs).len(), 2);
+
+        // the next obstacle comes first, the ones behind the dinosaur are ignored
+        let obstacles = vec![
+            Obstacle::new(&params, 900.0, params.obstacle_speed, 0.0, ObstacleEntityType::Hole),
+            Obstacle::new(&params, 500.0, params.obstacle_speed, 0.0, ObstacleEntityType::Cactus),
+            Obstacle::new(&params, -1000.0, params.obstacle_speed, 0.0, ObstacleEntityType::Cactus),
+        ];
+        let inputs = network.get_inputs(&obstacles, &dinosaur, 0.0);
+        assert_eq!(inputs[4..7], [1.0, 0.0, 0.0]);
+        assert_eq!(inputs[NB_INPUT_PER_OBSTACLE + 4..NB_INPUT_PER_OBSTACLE + 7], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_network_mutation_and_crossover() {
+        let mut params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let parent_a = NeuralNetwork::new(&params, &mut rng);
+        let parent_b = NeuralNetwork::new(&params, &mut rng);
+
+        // the mutation only moves the weights
+        let child = parent_a.mutate(&params, &mut rng);
+        assert_ne!(child, parent_a);
+        assert_eq!(child.get_nb_parameter(), parent_a.get_nb_parameter());
+        params.network_weight_mutation_rate = 0.0;
+        assert_eq!(parent_a.mutate(&params, &mut rng), parent_a);
+
+        // every neurone comes from one of the parents
+        let child = parent_a.crossover(&parent_b, &params, &mut rng);
+        for (i, layer) in child.layers.iter().enumerate() {
+            for (j, weights) in layer.weights.iter().enumerate() {
+                assert!(*weights == parent_a.layers[i].weights[j] || *weights == parent_b.layers[i].weights[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_network_race() {
+        // the networks race like the brains
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let networks : Vec<NeuralNetwork> = (0..4).map(|_| NeuralNetwork::new(&params, &mut rng)).collect();
+        let mut race = Race::new(&params, "network", networks.clone());
+        race.run(Some(50));
+        // each network has the same score as in a race alone
+        for (network, score) in networks.into_iter().zip(race.get_scores()) {
+            let mut race_alone = Race::new(&params, "network", vec![network]);
+            race_alone.run(Some(50));
+            assert_eq!(race_alone.get_scores(), vec![score]);
+        }
+    }
+}
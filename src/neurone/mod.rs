@@ -5,7 +5,7 @@ use rand::{Rng, distributions::Uniform};
 use rand_pcg::Pcg64;
 use serde::{Serialize, Deserialize};
 
-use crate::{entity::Obstacle, utils::{check_collision, get_random_float, remove_indexes}, params::GameParameters};
+use crate::{entity::{Dinosaur, Obstacle, ObstacleEntityType}, utils::{check_collision, get_random_float, remove_indexes, uniform_crossover}, params::GameParameters};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -17,24 +17,145 @@ pub struct Neurone {
     
     pub activation_condition : NeuroneActivationCondition,
     pub activation : NeuroneActivation,
+    /// what the position of the neurone is relative to
+    #[serde(default)]
+    pub anchor : NeuroneAnchor,
+}
+
+/// what the position of a neurone is relative to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum NeuroneAnchor {
+    /// the neurone is fixed on the screen
+    #[default]
+    Screen,
+    /// the neurone moves with the dinosaur (its position is the one when the dinosaur is on the ground)
+    Dinosaur,
+}
+
+impl fmt::Display for NeuroneAnchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            NeuroneAnchor::Screen => write!(f, "Screen"),
+            NeuroneAnchor::Dinosaur => write!(f, "Dinosaur"),
+        }
+    }
+}
+
+impl NeuroneAnchor {
+    /// draw a random anchor for a neurone with this condition (dinosaur with the probability of the params)
+    /// NOTE : the rng is not used if the dinosaur anchor is disabled, so the brains are the same as before the anchors
+    /// (and as before the typed conditions, if they are disabled too)
+    /// NOTE : a neurone on the state of the dinosaur has no position, it is never anchored (the rng is not used)
+    pub fn new_random(activation_condition : NeuroneActivationCondition, params : &GameParameters, rng : &mut Pcg64) -> Self {
+        if !activation_condition.is_proprioceptive() && params.neurone_dinosaur_anchor_rate > 0.0 && rng.gen_bool(params.neurone_dinosaur_anchor_rate) {
+            NeuroneAnchor::Dinosaur
+        }else{
+            NeuroneAnchor::Screen
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            NeuroneAnchor::Screen => NeuroneAnchor::Dinosaur,
+            NeuroneAnchor::Dinosaur => NeuroneAnchor::Screen,
+        }
+    }
 }
 
 /// Neurone activation condition
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NeuroneActivationCondition {
+    /// no obstacle in the neurone
     Air,
+    /// any obstacle in the neurone
     Obstacle,
+    /// a cactus or a rock in the neurone
+    CactusOrRock,
+    /// a pterodactyle (with or without rock) in the neurone
+    Pterodactyle,
+    /// a hole in the neurone (the only neurone that can go down to the hole)
+    Hole,
+    /// the dinosaur is on the ground (the position of the neurone doesn't matter for the dinosaur conditions)
+    OnGround,
+    /// the dinosaur goes up
+    Rising,
+    /// the dinosaur goes down
+    Falling,
+    /// the dinosaur is bending
+    Bending,
+    /// the game is past the half of its acceleration (see FAST_GAME_SPEED)
+    FastGame,
 }
 
+/// the speed of the game (from 0 to 1) from which the fast game neurones are activated
+pub const FAST_GAME_SPEED: f64 = 0.5;
+
 impl fmt::Display for NeuroneActivationCondition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             NeuroneActivationCondition::Air => write!(f, "Air"),
             NeuroneActivationCondition::Obstacle => write!(f, "Obstacle"),
+            NeuroneActivationCondition::CactusOrRock => write!(f, "Cactus/Rock"),
+            NeuroneActivationCondition::Pterodactyle => write!(f, "Pterodactyle"),
+            NeuroneActivationCondition::Hole => write!(f, "Hole"),
+            NeuroneActivationCondition::OnGround => write!(f, "On ground"),
+            NeuroneActivationCondition::Rising => write!(f, "Rising"),
+            NeuroneActivationCondition::Falling => write!(f, "Falling"),
+            NeuroneActivationCondition::Bending => write!(f, "Bending"),
+            NeuroneActivationCondition::FastGame => write!(f, "Fast game"),
         }
     }
 }
 
+impl NeuroneActivationCondition {
+    /// if the neurone see this type of obstacle (the air is stopped by any obstacle)
+    pub fn is_seen(&self, type_ : &ObstacleEntityType) -> bool {
+        match self {
+            NeuroneActivationCondition::Air | NeuroneActivationCondition::Obstacle => true,
+            NeuroneActivationCondition::CactusOrRock => matches!(type_, ObstacleEntityType::Cactus | ObstacleEntityType::Rock),
+            NeuroneActivationCondition::Pterodactyle => matches!(type_, ObstacleEntityType::Pterodactyle | ObstacleEntityType::PterodactyleWithRock),
+            NeuroneActivationCondition::Hole => *type_ == ObstacleEntityType::Hole,
+            _ => false,
+        }
+    }
+
+    /// if the condition is on the state of the dinosaur or of the game (not on the obstacles in the neurone)
+    pub fn is_proprioceptive(&self) -> bool {
+        matches!(
+            self,
+            NeuroneActivationCondition::OnGround | NeuroneActivationCondition::Rising | NeuroneActivationCondition::Falling |
+            NeuroneActivationCondition::Bending | NeuroneActivationCondition::FastGame
+        )
+    }
+
+    /// if the condition on the dinosaur or the game is met (always false for the conditions on the obstacles)
+    pub fn is_felt(&self, dinosaur : &Dinosaur, speed : f64) -> bool {
+        match self {
+            NeuroneActivationCondition::OnGround => dinosaur.y <= 0.0,
+            NeuroneActivationCondition::Rising => dinosaur.y > 0.0 && dinosaur.velocity > 0.0,
+            NeuroneActivationCondition::Falling => dinosaur.y > 0.0 && dinosaur.velocity <= 0.0,
+            NeuroneActivationCondition::Bending => dinosaur.is_bending,
+            NeuroneActivationCondition::FastGame => speed >= FAST_GAME_SPEED,
+            _ => false,
+        }
+    }
+
+    /// get the minimum y of a neurone with this condition
+    /// NOTE : only the hole neurone can go down to the hole, the others must not detect it
+    pub fn get_min_y(&self, params : &GameParameters) -> f64 {
+        match self {
+            NeuroneActivationCondition::Hole => 0.0,
+            _ => params.hole_height as f64 + 5.0,
+        }
+    }
+
+    /// draw a random condition among the possible ones (see GameParameters::get_neurone_conditions)
+    /// NOTE : the index is drawn as an u32 like the air or obstacle draw before the typed conditions (an usize is drawn differently)
+    pub fn new_random(conditions : &[NeuroneActivationCondition], rng : &mut Pcg64) -> Self {
+        conditions[rng.gen_range(0..conditions.len() as u32) as usize]
+    }
+}
+
 /// if the activation, force to not jump or jump
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NeuroneActivation {
@@ -52,16 +173,23 @@ impl fmt::Display for NeuroneActivation {
 }
 
 /// get the color of the neurone depending on its activation and its condition
+/// NOTE : the typed conditions are tinted in blue
 pub fn get_color_from_activation(activation : NeuroneActivation, condition : NeuroneActivationCondition) -> Color {
-    let alpha = 
+    let (blue, alpha) = 
             match condition {
-                NeuroneActivationCondition::Air => 0.5,
-                NeuroneActivationCondition::Obstacle => 1.0,
+                NeuroneActivationCondition::Air => (0.0, 0.5),
+                NeuroneActivationCondition::Obstacle => (0.0, 1.0),
+                NeuroneActivationCondition::CactusOrRock => (0.5, 1.0),
+                NeuroneActivationCondition::Pterodactyle => (1.0, 1.0),
+                NeuroneActivationCondition::Hole => (1.0, 0.5),
+                // the conditions on the dinosaur are pale
+                NeuroneActivationCondition::OnGround | NeuroneActivationCondition::Rising | NeuroneActivationCondition::Falling => (0.5, 0.3),
+                NeuroneActivationCondition::Bending | NeuroneActivationCondition::FastGame => (1.0, 0.3),
             };
 
     match activation {
-        NeuroneActivation::Activate => Color { r : 0.0, g : 1.0, b : 0.0, a : alpha},
-        NeuroneActivation::PreventActivate => Color { r : 1.0, g : 0.0, b : 0.0, a : alpha}
+        NeuroneActivation::Activate => Color { r : 0.0, g : 1.0, b : blue, a : alpha},
+        NeuroneActivation::PreventActivate => Color { r : 1.0, g : 0.0, b : blue, a : alpha}
     }
 }
 
@@ -80,18 +208,26 @@ impl Neurone {
             height : params.neurone_height,
             activation_condition,
             activation,
+            anchor : NeuroneAnchor::Screen,
         }
     }
 
     /// create a totaly new random neurone
+    /// NOTE : with the typed conditions, the condition is drawn first (the position depends on it),
+    /// without them the position is drawn first (the order before the typed conditions, same brains with the same seed)
     pub fn new_random(params : &GameParameters, rng : &mut Pcg64) -> Self {
-        let x = get_random_float(0.0, (params.game_width - params.neurone_width) as f64, rng);
-        let y = get_random_float(params.hole_height as f64 + 5.0, (params.game_height - params.neurone_height) as f64, rng);
-        let activation_condition = 
-            match rng.gen_range(0..2) {
-                0 => NeuroneActivationCondition::Air,
-                _ => NeuroneActivationCondition::Obstacle,
-            };
+        let conditions = params.get_neurone_conditions();
+        let max_y = (params.game_height - params.neurone_height) as f64;
+        let (activation_condition, x, y);
+        if params.neurone_typed_conditions {
+            activation_condition = NeuroneActivationCondition::new_random(&conditions, rng);
+            x = get_random_float(0.0, (params.game_width - params.neurone_width) as f64, rng);
+            y = get_random_float(activation_condition.get_min_y(params), max_y, rng);
+        }else{
+            x = get_random_float(0.0, (params.game_width - params.neurone_width) as f64, rng);
+            y = get_random_float(NeuroneActivationCondition::Obstacle.get_min_y(params), max_y, rng);
+            activation_condition = NeuroneActivationCondition::new_random(&conditions, rng);
+        }
         let activation =
             match rng.gen_range(0..2) {
                 0 => NeuroneActivation::Activate,
@@ -104,27 +240,33 @@ impl Neurone {
             height : params.neurone_height,
             activation_condition,
             activation,
+            anchor : NeuroneAnchor::new_random(activation_condition, params, rng),
         }
     }
 
     /// mutate this neurone
+    /// NOTE : with the typed conditions, the condition is mutated first, the position depends on it (only the hole neurone can go down to the hole),
+    /// without them it is mutated after the position (the order before the typed conditions)
     pub fn mutate(&mut self, params : &GameParameters, rng : &mut Pcg64) {
+        // mutate the activation condition if rng say so
+        if params.neurone_typed_conditions && rng.gen_bool(params.neurone_change_action_mutation_rate) {
+            self.activation_condition = NeuroneActivationCondition::new_random(&params.get_neurone_conditions(), rng);
+        }
+
         // get the range of the mutation for x and y (we don't want to go out of the screen)
+        let max_y_limit = (params.game_height - params.neurone_height) as f64;
+        let min_y_limit = self.activation_condition.get_min_y(params);
         let min_x = (self.x - params.neurone_x_mutation_range).max(0.0);
         let max_x = (self.x + params.neurone_x_mutation_range).min((params.game_width - params.neurone_width) as f64);
-        let min_y = (self.y - params.neurone_y_mutation_range).max(params.hole_height as f64 + 5.0);// we don't want to go under the hole
-        let max_y = (self.y + params.neurone_y_mutation_range).min((params.game_height - params.neurone_height) as f64);
+        let min_y = (self.y - params.neurone_y_mutation_range).clamp(min_y_limit, max_y_limit);
+        let max_y = (self.y + params.neurone_y_mutation_range).clamp(min_y_limit, max_y_limit);
 
         self.x = get_random_float(min_x, max_x, rng);
-        self.y = get_random_float(min_y, max_y, rng);
+        // the neurone can be far under its new minimum (a hole neurone that changed its condition)
+        self.y = if min_y < max_y { get_random_float(min_y, max_y, rng) } else { min_y };
 
-        // mutate the activation condition if rng say so
-        if rng.gen_bool(params.neurone_change_action_mutation_rate) {
-            self.activation_condition = 
-                match rng.gen_range(0..2) {
-                    0 => NeuroneActivationCondition::Air,
-                    _ => NeuroneActivationCondition::Obstacle,
-                };
+        if !params.neurone_typed_conditions && rng.gen_bool(params.neurone_change_action_mutation_rate) {
+            self.activation_condition = NeuroneActivationCondition::new_random(&params.get_neurone_conditions(), rng);
         }
 
         // mutate the activation if rng say so
@@ -135,35 +277,48 @@ impl Neurone {
                     _ => NeuroneActivation::PreventActivate,
                 };
         }
+
+        // mutate the anchor if rng say so (the position doesn't change, it is the one with the dinosaur on the ground)
+        // a neurone on the state of the dinosaur has no position, so no anchor
+        if self.activation_condition.is_proprioceptive() {
+            self.anchor = NeuroneAnchor::Screen;
+        }else if params.neurone_change_anchor_mutation_rate > 0.0 && rng.gen_bool(params.neurone_change_anchor_mutation_rate) {
+            self.anchor = self.anchor.toggle();
+        }
+    }
+
+    /// get the position of the neurone on the screen (bottom left point), it moves with the dinosaur if it is anchored to it
+    pub fn get_position(&self, dinosaur : &Dinosaur) -> (f64, f64) {
+        match self.anchor {
+            NeuroneAnchor::Screen => (self.x, self.y),
+            NeuroneAnchor::Dinosaur => (self.x, self.y + dinosaur.y),
+        }
     }
 
     /// get the activation of the neurone if its condition is met
-    pub fn get_activation(&self, obstacles : &Vec<Obstacle>) -> Option<NeuroneActivation> {
-        let mut is_colision = false;
-        for obstacle in obstacles {
-            if check_collision(
-                self.x,
-                self.y,
+    /// NOTE : a typed condition only see its type of obstacle, the speed of the game goes from 0 to 1 (see Game::get_speed)
+    pub fn get_activation(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> Option<NeuroneActivation> {
+        if self.activation_condition.is_proprioceptive() {
+            return self.activation_condition.is_felt(dinosaur, speed).then_some(self.activation);
+        }
+        let (x, y) = self.get_position(dinosaur);
+        let is_colision = obstacles.iter().any(|obstacle| {
+            self.activation_condition.is_seen(&obstacle.type_) && check_collision(
+                x,
+                y,
                 self.width,
                 self.height,
                 obstacle.x,
                 obstacle.y,
                 obstacle.width,
                 obstacle.height,
-            ) {
-                // if the neurone is activated by an obstacle
-                if self.activation_condition == NeuroneActivationCondition::Obstacle { 
-                    return Some(self.activation.clone());
-                }
-                is_colision = true;
-                break;
-            }
-        }
-        // if the neurone is activated by air and there is no colision
-        if !is_colision && self.activation_condition == NeuroneActivationCondition::Air {
-            return Some(self.activation.clone());
+            )
+        });
+        // the air is activated without colision, the others with a colision
+        if is_colision == (self.activation_condition != NeuroneActivationCondition::Air) {
+            Some(self.activation)
         }else{
-            return None;
+            None
         }
     }
 
@@ -174,13 +329,32 @@ impl Neurone {
         let dinausor_y = params.dinausor_height as f64 / 2.0;
         let neurone_x = self.x + params.neurone_width as f64 / 2.0;
         let neurone_y = self.y + params.neurone_height as f64 / 2.0;
+        // the neurone on the state of the dinosaur doesn't look around, only its flat cost is paid
+        if self.activation_condition.is_proprioceptive() {
+            return params.neuron_cost_flat as f64;
+        }
         let distance = ((dinausor_x - neurone_x).powi(2) + (dinausor_y - neurone_y).powi(2)).sqrt();
-        distance * params.neuron_cost_mult as f64 + params.neuron_cost_flat as f64    
+        // the neurone that moves with the dinosaur see more, it cost more
+        let anchor_cost = match self.anchor {
+            NeuroneAnchor::Screen => 0,
+            NeuroneAnchor::Dinosaur => params.neuron_dinosaur_anchor_cost_flat,
+        };
+        distance * params.neuron_cost_mult as f64 + params.neuron_cost_flat as f64 + anchor_cost as f64
+    }
+
+    /// get the compatibility distance with another neurone (for the speciation)
+    /// NOTE : the distance between the positions, plus the share of the condition, activation and anchor that differ
+    pub fn get_distance(&self, other : &Neurone, params : &GameParameters) -> f64 {
+        let position_distance = ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt();
+        let nb_difference = (self.activation_condition != other.activation_condition) as u8
+            + (self.activation != other.activation) as u8
+            + (self.anchor != other.anchor) as u8;
+        position_distance * params.speciation_position_coefficient + nb_difference as f64 / 3.0 * params.speciation_web_coefficient
     }
 }
 
 /// action of the neurone web
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
 pub enum NeuroneWebAction {
     Jump,
     Bend,
@@ -199,13 +373,21 @@ impl fmt::Display for NeuroneWebAction {
 
 /// get the color of the neurone web depending on its action
 pub fn get_color_from_action(action : &NeuroneWebAction) -> Color{
-    let color_action = match action {
+    match action {
         NeuroneWebAction::Jump => Color::from_rgb8(0, 0, 0),
         NeuroneWebAction::Bend => Color::from_rgb8(255, 0, 255),
         NeuroneWebAction::Unbend => Color::from_rgb8(0, 0, 255),
-    };
+    }
+}
 
-    color_action
+/// the state of a neurone web for the current obstacles
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeuroneWebState {
+    /// for each neurone, if its condition is met
+    pub met_conditions : Vec<bool>,
+    /// the index of the prevent activate neurone that stop the web
+    pub veto : Option<usize>,
+    pub is_activated : bool,
 }
 
 /// a web of neurone
@@ -223,22 +405,34 @@ impl NeuroneWeb {
         // get the number of neurones
         let nb_neurones = rng.gen_range(params.neurone_web_creation_nb_neurones_min..params.neurone_web_creation_nb_neurones_max);
         // gain of performance by declaring the distribution outside of the loop
+        let conditions = params.get_neurone_conditions();
         let x_dist = Uniform::from(0.0..(params.game_width - params.neurone_width) as f64);
         let y_dist = Uniform::from((params.hole_height as f64 + 5.0)..(params.game_height - params.neurone_height) as f64);
+        let hole_y_dist = Uniform::from(0.0..(params.game_height - params.neurone_height) as f64);
         for _ in 0..nb_neurones {
-            let x = rng.sample(x_dist);
-            let y = rng.sample(y_dist);
-            let activation_condition = 
-                match rng.gen_range(0..2) {
-                    0 => NeuroneActivationCondition::Air,
-                    _ => NeuroneActivationCondition::Obstacle,
+            // the position is drawn first without the typed conditions (see Neurone::new_random)
+            let (activation_condition, x, y);
+            if params.neurone_typed_conditions {
+                activation_condition = NeuroneActivationCondition::new_random(&conditions, rng);
+                x = rng.sample(x_dist);
+                y = if activation_condition == NeuroneActivationCondition::Hole {
+                    rng.sample(hole_y_dist)
+                }else{
+                    rng.sample(y_dist)
                 };
+            }else{
+                x = rng.sample(x_dist);
+                y = rng.sample(y_dist);
+                activation_condition = NeuroneActivationCondition::new_random(&conditions, rng);
+            }
             let activation =
                 match rng.gen_range(0..2) {
                     0 => NeuroneActivation::Activate,
                     _ => NeuroneActivation::PreventActivate,
                 };
-            neurones.push(Neurone::new(params, x, y, activation_condition, activation));
+            let mut neurone = Neurone::new(params, x, y, activation_condition, activation);
+            neurone.anchor = NeuroneAnchor::new_random(activation_condition, params, rng);
+            neurones.push(neurone);
         }
         // get the action of the neurone web
         let action_i = rng.gen_range(0..params.commands.len());
@@ -278,14 +472,29 @@ impl NeuroneWeb {
     }
 
 
-    pub fn is_activated(&self, obstacles : &Vec<Obstacle>) -> bool {
+    /// create a new neurone web from two parents (uniform crossover of the neurones, action of one of the parents)
+    pub fn crossover(&self, other : &NeuroneWeb, rng : &mut Pcg64) -> Self {
+        let neurones = uniform_crossover(&self.neurones, &other.neurones, rng);
+        let action = if rng.gen_bool(0.5) {
+            self.action.clone()
+        }else{
+            other.action.clone()
+        };
+
+        Self {
+            neurones,
+            action,
+        }
+    }
+
+    pub fn is_activated(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> bool {
         let mut active = false;
         for neurone in &self.neurones {
             // get the activation of the neurone
-            let activation = neurone.get_activation(obstacles);
+            let activation = neurone.get_activation(obstacles, dinosaur, speed);
             // if the neurone is activated, we check if it is a force to not jump
-            if activation.is_some() {
-                if activation.unwrap() == NeuroneActivation::PreventActivate { // force the not jump
+            if let Some(activation) = activation {
+                if activation == NeuroneActivation::PreventActivate { // force the not jump
                     return false;
                 }else{
                     // if we have a jump and no force to not jump, we can jump
@@ -297,6 +506,27 @@ impl NeuroneWeb {
         active
     }
 
+    /// get the detailed state of the web for the obstacles (for the display)
+    /// NOTE : is_activated is kept apart, it is faster for the training
+    pub fn get_state(&self, obstacles : &[Obstacle], dinosaur : &Dinosaur, speed : f64) -> NeuroneWebState {
+        let met_conditions : Vec<bool> = self.neurones.iter()
+            .map(|neurone| neurone.get_activation(obstacles, dinosaur, speed).is_some())
+            .collect();
+        // the first prevent activate neurone with its condition met stop the web
+        let veto = self.neurones.iter()
+            .zip(&met_conditions)
+            .position(|(neurone, met)| *met && neurone.activation == NeuroneActivation::PreventActivate);
+        let is_activated = veto.is_none() && self.neurones.iter()
+            .zip(&met_conditions)
+            .any(|(neurone, met)| *met && neurone.activation == NeuroneActivation::Activate);
+
+        NeuroneWebState {
+            met_conditions,
+            veto,
+            is_activated,
+        }
+    }
+
     /// get the energy of the neurone web
     pub fn get_energy(&self, params : &GameParameters) -> f64 {
         let mut energy = 0.0;
@@ -306,6 +536,21 @@ impl NeuroneWeb {
         energy * params.neuron_web_cost_mult as f64 + params.neuron_web_cost_flat as f64
     }
 
+    /// get the compatibility distance with another neurone web (for the speciation)
+    /// NOTE : the neurones are compared by position in the web, a neurone without pair counts as a full difference
+    pub fn get_distance(&self, other : &NeuroneWeb, params : &GameParameters) -> f64 {
+        let action_distance = if self.action == other.action { 0.0 } else { params.speciation_web_coefficient };
+        let nb_neurone = self.neurones.len().max(other.neurones.len());
+        if nb_neurone == 0 {
+            return action_distance;
+        }
+        let unmatched_distance = self.neurones.len().abs_diff(other.neurones.len()) as f64 * params.speciation_web_coefficient;
+        let matched_distance : f64 = self.neurones.iter()
+            .zip(&other.neurones)
+            .map(|(neurone, other_neurone)| neurone.get_distance(other_neurone, params))
+            .sum();
+        action_distance + (unmatched_distance + matched_distance) / nb_neurone as f64
+    }
 
 }
 
@@ -313,7 +558,7 @@ impl NeuroneWeb {
 mod tests {
     use rand::SeedableRng;
 
-    use crate::{utils::str_to_u8_array};
+    use crate::{utils::str_to_u8_array, entity::ObstacleEntityType};
 
     use super::*;
 
@@ -347,4 +592,196 @@ mod tests {
         }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_typed_conditions(){
+        let params = GameParameters::new_default();
+        let dinosaur = Dinosaur::new_dinosaur(&params, 0.0);
+        let obstacles = vec![
+            Obstacle::new(&params, 100.0, params.obstacle_speed, 0.0, ObstacleEntityType::Cactus),
+            Obstacle::new(&params, 900.0, params.obstacle_speed, 0.0, ObstacleEntityType::Hole),
+        ];
+        let on_obstacle = |obstacle : &Obstacle, condition| Neurone::new(&params, obstacle.x, obstacle.y, condition, NeuroneActivation::Activate);
+
+        // a typed neurone only see its type of obstacle
+        let on_cactus = |condition| on_obstacle(&obstacles[0], condition).get_activation(&obstacles, &dinosaur, 0.0).is_some();
+        assert!(on_cactus(NeuroneActivationCondition::Obstacle));
+        assert!(on_cactus(NeuroneActivationCondition::CactusOrRock));
+        assert!(!on_cactus(NeuroneActivationCondition::Pterodactyle));
+        assert!(!on_cactus(NeuroneActivationCondition::Hole));
+        assert!(!on_cactus(NeuroneActivationCondition::Air));
+
+        let on_hole = |condition| on_obstacle(&obstacles[1], condition).get_activation(&obstacles, &dinosaur, 0.0).is_some();
+        assert!(on_hole(NeuroneActivationCondition::Hole));
+        assert!(!on_hole(NeuroneActivationCondition::CactusOrRock));
+
+        // only the hole neurones go down to the hole
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let mut nb_hole_neurone = 0;
+        for _ in 0..500 {
+            let mut neurone = Neurone::new_random(&params, &mut rng);
+            neurone.mutate(&params, &mut rng);
+            assert!(params.get_neurone_conditions().contains(&neurone.activation_condition));
+            assert!(neurone.y >= neurone.activation_condition.get_min_y(&params));
+            if neurone.activation_condition == NeuroneActivationCondition::Hole {
+                nb_hole_neurone += 1;
+            }
+        }
+        assert!(nb_hole_neurone > 0);
+    }
+
+    #[test]
+    fn test_draw_order_without_typed_conditions(){
+        // without the typed conditions (and the anchors), the neurones are drawn like before them : position, then air or obstacle
+        let mut params = GameParameters::new_default();
+        params.neurone_typed_conditions = false;
+        params.neurone_proprioceptive_conditions = false;
+        params.neurone_dinosaur_anchor_rate = 0.0;
+        params.neurone_change_anchor_mutation_rate = 0.0;
+        let draw_condition = |rng : &mut Pcg64| match rng.gen_range(0..2) {
+            0 => NeuroneActivationCondition::Air,
+            _ => NeuroneActivationCondition::Obstacle,
+        };
+        let draw_activation = |rng : &mut Pcg64| match rng.gen_range(0..2) {
+            0 => NeuroneActivation::Activate,
+            _ => NeuroneActivation::PreventActivate,
+        };
+        let max_x = (params.game_width - params.neurone_width) as f64;
+        let min_y = params.hole_height as f64 + 5.0;
+        let max_y = (params.game_height - params.neurone_height) as f64;
+
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let mut expected_rng = rng.clone();
+        let web = NeuroneWeb::new_random(&params, &mut rng);
+        let nb_neurones = expected_rng.gen_range(params.neurone_web_creation_nb_neurones_min..params.neurone_web_creation_nb_neurones_max);
+        let x_dist = Uniform::from(0.0..max_x);
+        let y_dist = Uniform::from(min_y..max_y);
+        for neurone in &web.neurones {
+            assert_eq!(neurone.x, expected_rng.sample(x_dist));
+            assert_eq!(neurone.y, expected_rng.sample(y_dist));
+            assert_eq!(neurone.activation_condition, draw_condition(&mut expected_rng));
+            assert_eq!(neurone.activation, draw_activation(&mut expected_rng));
+        }
+        assert_eq!(web.neurones.len(), nb_neurones as usize);
+        assert_eq!(web.action, params.commands[expected_rng.gen_range(0..params.commands.len())]);
+
+        for _ in 0..50 {
+            let mut neurone = Neurone::new_random(&params, &mut rng);
+            assert_eq!(neurone.x, get_random_float(0.0, max_x, &mut expected_rng));
+            assert_eq!(neurone.y, get_random_float(min_y, max_y, &mut expected_rng));
+            assert_eq!(neurone.activation_condition, draw_condition(&mut expected_rng));
+            assert_eq!(neurone.activation, draw_activation(&mut expected_rng));
+
+            let old = neurone;
+            neurone.mutate(&params, &mut rng);
+            let x = get_random_float((old.x - params.neurone_x_mutation_range).max(0.0), (old.x + params.neurone_x_mutation_range).min(max_x), &mut expected_rng);
+            let y = get_random_float((old.y - params.neurone_y_mutation_range).max(min_y), (old.y + params.neurone_y_mutation_range).min(max_y), &mut expected_rng);
+            assert_eq!((neurone.x, neurone.y), (x, y));
+            if expected_rng.gen_bool(params.neurone_change_action_mutation_rate) {
+                assert_eq!(neurone.activation_condition, draw_condition(&mut expected_rng));
+            }
+            if expected_rng.gen_bool(params.neurone_change_action_mutation_rate) {
+                assert_eq!(neurone.activation, draw_activation(&mut expected_rng));
+            }
+        }
+        assert_eq!(rng, expected_rng);
+    }
+
+    #[test]
+    fn test_neurone_web_state(){
+        let params = GameParameters::new_default();
+        let dinosaur = Dinosaur::new_dinosaur(&params, 0.0);
+        let obstacles = vec![Obstacle::new(&params, 100.0, params.obstacle_speed, 0.0, ObstacleEntityType::Cactus)];
+        let obstacle = &obstacles[0];
+        let on_obstacle = |activation| Neurone::new(&params, obstacle.x, obstacle.y, NeuroneActivationCondition::Obstacle, activation);
+        let far = |activation| Neurone::new(&params, 600.0, 300.0, NeuroneActivationCondition::Obstacle, activation);
+
+        let mut web = NeuroneWeb {
+            neurones : vec![far(NeuroneActivation::Activate), on_obstacle(NeuroneActivation::Activate)],
+            action : NeuroneWebAction::Jump,
+        };
+        let state = web.get_state(&obstacles, &dinosaur, 0.0);
+        assert_eq!(state.met_conditions, vec![false, true]);
+        assert_eq!(state.veto, None);
+        assert!(state.is_activated);
+        assert_eq!(state.is_activated, web.is_activated(&obstacles, &dinosaur, 0.0));
+
+        // the prevent activate neurone stop the web
+        web.neurones.push(on_obstacle(NeuroneActivation::PreventActivate));
+        let state = web.get_state(&obstacles, &dinosaur, 0.0);
+        assert_eq!(state.met_conditions, vec![false, true, true]);
+        assert_eq!(state.veto, Some(2));
+        assert!(!state.is_activated);
+        assert_eq!(state.is_activated, web.is_activated(&obstacles, &dinosaur, 0.0));
+
+        // the same web with random neurones
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        for _ in 0..100 {
+            let web = NeuroneWeb::new_random(&params, &mut rng);
+            assert_eq!(web.get_state(&obstacles, &dinosaur, 0.0).is_activated, web.is_activated(&obstacles, &dinosaur, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_dinosaur_anchor(){
+        let params = GameParameters::new_default();
+        let obstacles = vec![Obstacle::new(&params, 100.0, params.obstacle_speed, 0.0, ObstacleEntityType::Cactus)];
+        let mut dinosaur = Dinosaur::new_dinosaur(&params, 0.0);
+        let mut neurone = Neurone::new(&params, obstacles[0].x, obstacles[0].y, NeuroneActivationCondition::Obstacle, NeuroneActivation::Activate);
+        let energy = neurone.get_energy(&params);
+        neurone.anchor = NeuroneAnchor::Dinosaur;
+        assert_eq!(neurone.get_energy(&params), energy + params.neuron_dinosaur_anchor_cost_flat as f64);
+
+        // the neurone moves with the dinosaur, it doesn't see the obstacle anymore when the dinosaur is in the air
+        assert!(neurone.get_activation(&obstacles, &dinosaur, 0.0).is_some());
+        dinosaur.y += 200.0;
+        assert_eq!(neurone.get_position(&dinosaur), (neurone.x, neurone.y + 200.0));
+        assert!(neurone.get_activation(&obstacles, &dinosaur, 0.0).is_none());
+
+        // the screen neurone stays in place
+        neurone.anchor = neurone.anchor.toggle();
+        assert!(neurone.get_activation(&obstacles, &dinosaur, 0.0).is_some());
+    }
+
+    #[test]
+    fn test_proprioceptive_conditions(){
+        let params = GameParameters::new_default();
+        let obstacles = vec![Obstacle::new(&params, 100.0, params.obstacle_speed, 0.0, ObstacleEntityType::Cactus)];
+        let mut dinosaur = Dinosaur::new_dinosaur(&params, 0.0);
+        let felt = |condition, dinosaur : &Dinosaur, speed| {
+            // far from the obstacle, the position doesn't matter
+            Neurone::new(&params, 600.0, 300.0, condition, NeuroneActivation::Activate).get_activation(&obstacles, dinosaur, speed).is_some()
+        };
+
+        assert!(felt(NeuroneActivationCondition::OnGround, &dinosaur, 0.0));
+        assert!(!felt(NeuroneActivationCondition::Rising, &dinosaur, 0.0));
+        assert!(!felt(NeuroneActivationCondition::FastGame, &dinosaur, 0.0));
+        assert!(felt(NeuroneActivationCondition::FastGame, &dinosaur, 1.0));
+
+        // the jump goes up then down
+        assert!(dinosaur.jump());
+        dinosaur.update(0.1);
+        assert!(felt(NeuroneActivationCondition::Rising, &dinosaur, 0.0));
+        assert!(!felt(NeuroneActivationCondition::OnGround, &dinosaur, 0.0));
+        dinosaur.update(0.6);
+        assert!(felt(NeuroneActivationCondition::Falling, &dinosaur, 0.0));
+        assert!(!felt(NeuroneActivationCondition::Bending, &dinosaur, 0.0));
+
+        // the neurone on the dinosaur only pays its flat cost
+        let mut neurone = Neurone::new(&params, 600.0, 300.0, NeuroneActivationCondition::Bending, NeuroneActivation::Activate);
+        assert_eq!(neurone.get_energy(&params), params.neuron_cost_flat as f64);
+        // it has no position, so no anchor (and no anchor cost)
+        neurone.anchor = NeuroneAnchor::Dinosaur;
+        assert_eq!(neurone.get_energy(&params), params.neuron_cost_flat as f64);
+        let mut anchor_params = params.clone();
+        anchor_params.neurone_dinosaur_anchor_rate = 1.0;
+        anchor_params.neurone_change_anchor_mutation_rate = 1.0;
+        anchor_params.neurone_change_action_mutation_rate = 0.0;
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        neurone.mutate(&anchor_params, &mut rng);
+        assert_eq!(neurone.anchor, NeuroneAnchor::Screen);
+        for _ in 0..100 {
+            let neurone = Neurone::new_random(&anchor_params, &mut rng);
+            assert_eq!(neurone.anchor == NeuroneAnchor::Screen, neurone.activation_condition.is_proprioceptive());
+        }
+    }
+}
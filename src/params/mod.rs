@@ -2,15 +2,44 @@ use std::vec;
 
 use serde::{Serialize, Deserialize};
 
-use crate::{neurone::NeuroneWebAction, entity::ObstacleGenerateType};
+use crate::{neurone::{NeuroneWebAction, NeuroneActivationCondition}, entity::ObstacleGenerateType, selection::SelectionType, error::{Error, Result}};
 
+/// read a params json file (game params or training config)
+fn read_params_file<T : serde::de::DeserializeOwned>(path: &str) -> Result<T> {
+    let file = std::fs::File::open(path).map_err(|source| Error::Read { path : path.to_string(), source })?;
+    let reader = std::io::BufReader::new(file);
+
+    serde_json::from_reader(reader).map_err(|source| Error::ParamsParse { path : path.to_string(), source })
+}
 
-pub const TRAINING_NB_GENERATION: u64 = 3_000_000;
-/// the score limit to stop the training (if the brain reach this score, we actually consider it as a good brain)
-pub const LIMIT_SCORE: u64 = 400;
-/// the interval to save the result (in number of generation)
-pub const INTERVAL_TO_SAVE_RESULT: u64 = 100;
-pub const RESULT_FOLDER_PATH: &str = "./ressources/results/";
+
+/// the budget of a training run (saved in the training folder next to the params)
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct TrainingConfig {
+    /// the number of generation to run
+    pub nb_generation: u64,
+    /// the score limit to stop the training (if the brain reach this score, we actually consider it as a good brain)
+    pub limit_score: u64,
+    /// the interval to save the result (in number of generation)
+    pub interval_to_save_result: u64,
+    /// the folder where a new training is created if no folder is given
+    pub result_folder_path: String,
+}
+
+impl TrainingConfig {
+    pub fn new_default() -> Self {
+        TrainingConfig {
+            nb_generation: 3_000_000,
+            limit_score: 400,
+            interval_to_save_result: 100,
+            result_folder_path: "./ressources/results/".to_string(),
+        }
+    }
+
+    pub fn new_from_file(path: &str) -> Result<Self> {
+        read_params_file(path)
+    }
+}
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct GameParameters {
@@ -27,9 +56,6 @@ pub struct GameParameters {
     pub gravity: u64,
     /// the commands that the neurone web can do (and the obstacle generation)
     pub commands: Vec<NeuroneWebAction>,
-    /// the obstacle that can be generated (depending on the commands, calculated at the start of the program)
-    #[serde(skip)]
-    pub obstacle_generate_types: Vec<ObstacleGenerateType>,
 
     // ------------------- Game Timing --------------------
     pub dinausor_jump_velocity: f64,
@@ -85,27 +111,213 @@ pub struct GameParameters {
 
     pub neurone_x_mutation_range: f64,
     pub neurone_y_mutation_range: f64,
+    /// if the neurones can be activated by one type of obstacle (cactus/rock, pterodactyle, hole)
+    /// NOTE : without them, the neurones are drawn like before the typed conditions (same brains with the same seed)
+    #[serde(default)]
+    pub neurone_typed_conditions: bool,
+
+    /// the probability for a new neurone to move with the dinosaur (instead of being fixed on the screen)
+    #[serde(default)]
+    pub neurone_dinosaur_anchor_rate: f64,
+    /// the probability for a neurone to switch between the screen and the dinosaur anchor
+    #[serde(default)]
+    pub neurone_change_anchor_mutation_rate: f64,
+    /// if the neurones can sense the state of the dinosaur (on ground, rising, falling, bending) and the speed of the game
+    #[serde(default)]
+    pub neurone_proprioceptive_conditions: bool,
+
+    /// the probability for a new brain to be the crossover of two parents (before its mutation)
+    #[serde(default)]
+    pub crossover_rate: f64,
+    #[serde(default)]
+    pub crossover_type: CrossoverType,
+    #[serde(default)]
+    pub crossover_parent_selection: ParentSelection,
+
+    // ---------------- controller -----------------
+    /// what plays the dinosaur and is trained (the neurone webs, or a neural network)
+    #[serde(default)]
+    pub controller: ControllerType,
+    /// the size of the hidden layers of the neural network
+    #[serde(default = "default_network_hidden_layers")]
+    pub network_hidden_layers: Vec<usize>,
+    /// the number of obstacles seen by the neural network in front of the dinosaur
+    #[serde(default = "default_network_nb_obstacle")]
+    pub network_nb_obstacle: usize,
+    /// the probability for each weight of the neural network to be mutated
+    #[serde(default = "default_network_weight_mutation_rate")]
+    pub network_weight_mutation_rate: f64,
+    /// the standard deviation of the gaussian noise added to a mutated weight
+    #[serde(default = "default_network_weight_mutation_std")]
+    pub network_weight_mutation_std: f64,
+    /// the energie of the neural network per unit of absolute weight
+    #[serde(default = "default_network_weight_cost_mult")]
+    pub network_weight_cost_mult: u64,
 
     //  ---------------- training -----------------
     pub training_nb_brain: u64,
     
     /// the number of brain to save at the end of the training, if < 0 we save all the brain
     pub max_nb_brain_to_save: i64,
+    /// the way the parents of the next generation are selected
+    #[serde(default)]
+    pub selection: SelectionType,
+
+    // speciation (NEAT-style, see Speciation)
+    /// the compatibility distance under which two brains are in the same species (None : no speciation)
+    #[serde(default)]
+    pub speciation_threshold: Option<f64>,
+    /// the distance of a web or a neurone without pair, of a different action, or of a neurone with a different condition, activation and anchor
+    #[serde(default = "default_speciation_web_coefficient")]
+    pub speciation_web_coefficient: f64,
+    /// the distance per px between two paired neurones
+    #[serde(default = "default_speciation_position_coefficient")]
+    pub speciation_position_coefficient: f64,
+    /// the distance per unit of mean weight difference between two neural networks
+    #[serde(default = "default_speciation_weight_coefficient")]
+    pub speciation_weight_coefficient: f64,
+    /// the number of generations without improvement after which a species is removed (except the best one)
+    #[serde(default = "default_speciation_stagnation_limit")]
+    pub speciation_stagnation_limit: u64,
     
     // energie cost
     pub neuron_cost_mult: u64,
     pub neuron_cost_flat: u64,
     pub neuron_web_cost_mult: u64,
     pub neuron_web_cost_flat: u64,
+    /// the cost added to a neurone that moves with the dinosaur
+    #[serde(default)]
+    pub neuron_dinosaur_anchor_cost_flat: u64,
     /// terrain generation (None : the seed is set to the param_land_seed, Some(5) : the seed is random every 5 generation)
     pub terrain_seed_generation_interval: Option<u64>,
+    /// the number of terrain each brain play every generation
+    #[serde(default = "default_training_nb_terrain")]
+    pub training_nb_terrain: u64,
+    /// how the scores of a brain on the different terrains are merged in one fitness
+    #[serde(default)]
+    pub fitness_aggregation: FitnessAggregation,
+}
+
+fn default_training_nb_terrain() -> u64 {
+    1
+}
+
+fn default_speciation_web_coefficient() -> f64 {
+    1.0
+}
+
+fn default_speciation_position_coefficient() -> f64 {
+    0.002
+}
+
+fn default_speciation_weight_coefficient() -> f64 {
+    1.0
+}
+
+fn default_speciation_stagnation_limit() -> u64 {
+    15
+}
+
+fn default_network_hidden_layers() -> Vec<usize> {
+    vec![8]
+}
+
+fn default_network_nb_obstacle() -> usize {
+    2
+}
+
+fn default_network_weight_mutation_rate() -> f64 {
+    0.1
+}
+
+fn default_network_weight_mutation_std() -> f64 {
+    0.2
+}
+
+fn default_network_weight_cost_mult() -> u64 {
+    1000
+}
+
+/// what plays the dinosaur (see Controller)
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub enum ControllerType {
+    /// the neurone webs of a Brain (the hitbox neurones)
+    #[default]
+    NeuroneWeb,
+    /// a small feed-forward NeuralNetwork on the next obstacles and the state of the dinosaur
+    NeuralNetwork,
+}
+
+/// the way two brains are recombined
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub enum CrossoverType {
+    /// each neurone web is taken from one of the parents
+    #[default]
+    Web,
+    /// the neurone webs at the same position are merged, each neurone is taken from one of the parents
+    Neurone,
+}
+
+/// the way the second parent of a crossover is chosen among the best brains
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub enum ParentSelection {
+    /// a random best brain
+    #[default]
+    Random,
+    /// the next best brain of the first parent
+    Rotate,
+}
+
+/// the way to merge the scores of a brain on several terrains
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub enum FitnessAggregation {
+    #[default]
+    Mean,
+    Min,
+    Median,
+    /// the percentile (between 0 and 100) of the scores (nearest rank)
+    Percentile(f64),
+}
+
+impl FitnessAggregation {
+    /// merge the scores into one fitness
+    /// NOTE : return 0 if there is no score
+    pub fn aggregate(&self, scores : &[u64]) -> f64 {
+        self.aggregate_values(&scores.iter().map(|score| *score as f64).collect::<Vec<f64>>())
+    }
+
+    /// merge any values (the fitness of the brains for the statistics) into one value
+    /// NOTE : return 0 if there is no value
+    pub fn aggregate_values(&self, values : &[f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        match self {
+            FitnessAggregation::Mean => sorted.iter().sum::<f64>() / sorted.len() as f64,
+            FitnessAggregation::Min => sorted[0],
+            FitnessAggregation::Median => {
+                let middle = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) {
+                    (sorted[middle - 1] + sorted[middle]) / 2.0
+                }else{
+                    sorted[middle]
+                }
+            },
+            FitnessAggregation::Percentile(percentile) => {
+                let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+                sorted[rank.max(1) - 1]
+            },
+        }
+    }
 }
 
 
 impl GameParameters {
 
     pub fn new_default() -> Self {
-        let mut params = GameParameters {
+        GameParameters {
             // Basics
             game_width: 1280,
             game_height: 720,
@@ -118,7 +330,6 @@ impl GameParameters {
             land_seed: "gra".to_string(),
             gravity: 2000,
             commands: vec![NeuroneWebAction::Jump/*, NeuroneWebAction::Bend, NeuroneWebAction::Unbend*/],
-            obstacle_generate_types: Vec::new(),
 
             // Game Timing
             dinausor_jump_velocity: 800.0,
@@ -167,37 +378,122 @@ impl GameParameters {
             neurone_x_mutation_range: 50.0,
             neurone_y_mutation_range: 50.0,
 
+            neurone_typed_conditions: true,
+
+            neurone_dinosaur_anchor_rate: 0.2,
+            neurone_change_anchor_mutation_rate: 0.001,
+            neurone_proprioceptive_conditions: true,
+
+            crossover_rate: 0.0,
+            crossover_type: CrossoverType::Web,
+            crossover_parent_selection: ParentSelection::Random,
+
+            // controller
+            controller: ControllerType::NeuroneWeb,
+            network_hidden_layers: default_network_hidden_layers(),
+            network_nb_obstacle: default_network_nb_obstacle(),
+            network_weight_mutation_rate: default_network_weight_mutation_rate(),
+            network_weight_mutation_std: default_network_weight_mutation_std(),
+            network_weight_cost_mult: default_network_weight_cost_mult(),
+
             // training
             training_nb_brain: 1000,
             max_nb_brain_to_save: 50,
+            selection: SelectionType::TopTies,
+            speciation_threshold: None,
+            speciation_web_coefficient: default_speciation_web_coefficient(),
+            speciation_position_coefficient: default_speciation_position_coefficient(),
+            speciation_weight_coefficient: default_speciation_weight_coefficient(),
+            speciation_stagnation_limit: default_speciation_stagnation_limit(),
             // energie cost
             neuron_cost_mult: 5,
             neuron_cost_flat : 100000,
             neuron_web_cost_mult: 15,
             neuron_web_cost_flat : 1000000,
+            neuron_dinosaur_anchor_cost_flat : 50000,
 
             terrain_seed_generation_interval : None,
+            training_nb_terrain : 1,
+            fitness_aggregation : FitnessAggregation::Mean,
             
-        };
-
-        params.obstacle_generate_types = params.get_obstacles_generation_type();
+        }
+    }
 
-        params
+    /// NOTE : the params that would make the training panic are refused (see validate)
+    pub fn new_from_file(path: &str) -> Result<Self> {
+        let params : GameParameters = read_params_file(path)?;
+        params.validate().map_err(|message| Error::ParamsParse { path : path.to_string(), source : serde::de::Error::custom(message) })?;
+        Ok(params)
     }
 
-    pub fn new_from_file(path: &str) -> Self {
-        let file = std::fs::File::open(path).expect("Unable to open parameter file");
-        let reader = std::io::BufReader::new(file);
+    /// check the values of the params (the probabilities must be between 0 and 1, the sizes must let the neurones fit in the game)
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let counts = [
+            ("training_nb_brain", self.training_nb_brain),
+            ("training_nb_terrain", self.training_nb_terrain),
+            ("game_fps", self.game_fps as u64),
+        ];
+        for (field, count) in counts {
+            if count == 0 {
+                return Err(format!("{} must be at least 1", field));
+            }
+        }
+        if self.commands.is_empty() {
+            return Err("commands must contain at least one action".to_string());
+        }
+        // the neurones are drawn between 0 and game_width - neurone_width (and above the holes)
+        if self.neurone_width >= self.game_width {
+            return Err(format!("game_width must be bigger than neurone_width ({}), not {}", self.neurone_width, self.game_width));
+        }
+        let min_game_height = self.hole_height as f64 + 5.0 + self.neurone_height as f64;
+        if self.game_height as f64 <= min_game_height {
+            return Err(format!("game_height must be bigger than hole_height + 5 + neurone_height ({}), not {}", min_game_height, self.game_height));
+        }
+        let probabilities = [
+            ("neurone_web_add_mutation_rate", self.neurone_web_add_mutation_rate),
+            ("neurone_web_remove_mutation_rate", self.neurone_web_remove_mutation_rate),
+            ("neurone_web_change_action_mutation_rate", self.neurone_web_change_action_mutation_rate),
+            ("neurone_add_mutation_rate", self.neurone_add_mutation_rate),
+            ("neurone_remove_mutation_rate", self.neurone_remove_mutation_rate),
+            ("neurone_change_action_mutation_rate", self.neurone_change_action_mutation_rate),
+            ("neurone_dinosaur_anchor_rate", self.neurone_dinosaur_anchor_rate),
+            ("neurone_change_anchor_mutation_rate", self.neurone_change_anchor_mutation_rate),
+            ("crossover_rate", self.crossover_rate),
+            ("network_weight_mutation_rate", self.network_weight_mutation_rate),
+        ];
+        for (field, probability) in probabilities {
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(format!("{} must be between 0 and 1, not {}", field, probability));
+            }
+        }
+        // the std of the gaussian noise of the mutation
+        if !self.network_weight_mutation_std.is_finite() || self.network_weight_mutation_std < 0.0 {
+            return Err(format!("network_weight_mutation_std must be a positive number, not {}", self.network_weight_mutation_std));
+        }
+
+        Ok(())
+    }
 
-        let mut params : GameParameters = serde_json::from_reader(reader).expect("Unable to parse parameter file");
-        params.obstacle_generate_types = params.get_obstacles_generation_type();
-        params
+    /// get the differences between two params (one line per field : "field : self value -> other value")
+    pub fn diff(&self, other : &GameParameters) -> Vec<String> {
+        let self_value = serde_json::to_value(self).unwrap();
+        let other_value = serde_json::to_value(other).unwrap();
+        let mut differences = Vec::new();
+        if let (Some(self_fields), Some(other_fields)) = (self_value.as_object(), other_value.as_object()) {
+            for (field, value) in self_fields {
+                let other_field_value = other_fields.get(field).unwrap_or(&serde_json::Value::Null);
+                if value != other_field_value {
+                    differences.push(format!("{} : {} -> {}", field, value, other_field_value));
+                }
+            }
+        }
 
+        differences
     }
 
-    /// vget the possible obstacles that can be generated given the params
+    /// get the possible obstacles that can be generated given the params (depending on the commands)
     /// WARN : not use HashMAp, it doesn't garantee the order, so the randomly repeatability is not garantee
-    fn get_obstacles_generation_type(&self) -> Vec<ObstacleGenerateType> {
+    pub fn get_obstacles_generation_type(&self) -> Vec<ObstacleGenerateType> {
         let mut vector = Vec::new();
         let commands = &self.commands;
         // jump obstacle
@@ -215,4 +511,142 @@ impl GameParameters {
 
         vector
     }
-}
\ No newline at end of file
+
+    /// get the conditions that a neurone can have given the params
+    /// NOTE : the typed conditions are only given for the obstacles that can be generated (depending on the commands)
+    pub fn get_neurone_conditions(&self) -> Vec<NeuroneActivationCondition> {
+        let generation_types = if self.neurone_typed_conditions {
+            self.get_obstacles_generation_type()
+        }else{
+            Vec::new()
+        };
+        let mut conditions = vec![NeuroneActivationCondition::Air];
+        if generation_types.iter().any(|type_| matches!(type_, ObstacleGenerateType::Cactus | ObstacleGenerateType::Rock | ObstacleGenerateType::RockAndPterodactyle | ObstacleGenerateType::RockAndHole)) {
+            conditions.push(NeuroneActivationCondition::CactusOrRock);
+        }
+        if generation_types.iter().any(|type_| matches!(type_, ObstacleGenerateType::RockAndPterodactyle | ObstacleGenerateType::Pterodactyle)) {
+            conditions.push(NeuroneActivationCondition::Pterodactyle);
+        }
+        if generation_types.contains(&ObstacleGenerateType::RockAndHole) {
+            conditions.push(NeuroneActivationCondition::Hole);
+        }
+        conditions.push(NeuroneActivationCondition::Obstacle);
+
+        // the state of the dinosaur (the jump only changes with the jump, the bending with the bend)
+        if self.neurone_proprioceptive_conditions {
+            if self.commands.contains(&NeuroneWebAction::Jump) {
+                conditions.extend([NeuroneActivationCondition::OnGround, NeuroneActivationCondition::Rising, NeuroneActivationCondition::Falling]);
+            }
+            if self.commands.contains(&NeuroneWebAction::Bend) {
+                conditions.push(NeuroneActivationCondition::Bending);
+            }
+            conditions.push(NeuroneActivationCondition::FastGame);
+        }
+
+        conditions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_diff() {
+        let params = GameParameters::new_default();
+        assert!(params.diff(&params.clone()).is_empty());
+
+        let mut other = params.clone();
+        other.gravity = 10;
+        other.land_seed = "other".to_string();
+        let differences = params.diff(&other);
+        assert_eq!(differences.len(), 2);
+        assert!(differences.contains(&"gravity : 2000 -> 10".to_string()));
+        assert!(differences.contains(&"land_seed : \"gra\" -> \"other\"".to_string()));
+    }
+
+    #[test]
+    fn test_params_validate() {
+        let mut params = GameParameters::new_default();
+        assert!(params.validate().is_ok());
+
+        params.crossover_rate = 1.5;
+        assert_eq!(params.validate(), Err("crossover_rate must be between 0 and 1, not 1.5".to_string()));
+        params.crossover_rate = 1.0;
+        params.neurone_change_anchor_mutation_rate = f64::NAN;
+        assert!(params.validate().is_err());
+        params.neurone_change_anchor_mutation_rate = 0.0;
+
+        params.network_weight_mutation_rate = 2.0;
+        assert!(params.validate().is_err());
+        params.network_weight_mutation_rate = 0.5;
+        for std in [-1.0, f64::INFINITY, f64::NAN] {
+            params.network_weight_mutation_std = std;
+            assert!(params.validate().is_err());
+        }
+        params.network_weight_mutation_std = 0.0;
+        assert!(params.validate().is_ok());
+
+        // the params which would make the training or the game panic
+        let changes : [fn(&mut GameParameters); 6] = [
+            |params : &mut GameParameters| params.training_nb_brain = 0,
+            |params : &mut GameParameters| params.training_nb_terrain = 0,
+            |params : &mut GameParameters| params.game_fps = 0,
+            |params : &mut GameParameters| params.commands.clear(),
+            |params : &mut GameParameters| params.game_width = params.neurone_width,
+            |params : &mut GameParameters| params.game_height = params.neurone_height,
+        ];
+        for change in changes {
+            let mut invalid = params.clone();
+            change(&mut invalid);
+            assert!(invalid.validate().is_err());
+        }
+        assert_eq!(
+            GameParameters { game_width : 10, ..params.clone() }.validate(),
+            Err(format!("game_width must be bigger than neurone_width ({}), not 10", params.neurone_width))
+        );
+    }
+
+    #[test]
+    fn test_neurone_conditions() {
+        let mut params = GameParameters::new_default();
+        params.neurone_proprioceptive_conditions = false;
+        params.commands = vec![NeuroneWebAction::Jump];
+        assert_eq!(params.get_neurone_conditions(), vec![
+            NeuroneActivationCondition::Air,
+            NeuroneActivationCondition::CactusOrRock,
+            NeuroneActivationCondition::Pterodactyle,
+            NeuroneActivationCondition::Hole,
+            NeuroneActivationCondition::Obstacle,
+        ]);
+
+        // only the pterodactyle can be avoided by bending
+        params.commands = vec![NeuroneWebAction::Bend, NeuroneWebAction::Unbend];
+        assert_eq!(params.get_neurone_conditions(), vec![
+            NeuroneActivationCondition::Air,
+            NeuroneActivationCondition::Pterodactyle,
+            NeuroneActivationCondition::Obstacle,
+        ]);
+
+        // the state of the dinosaur that the commands can change
+        params.neurone_proprioceptive_conditions = true;
+        assert_eq!(params.get_neurone_conditions()[3..], [
+            NeuroneActivationCondition::Bending,
+            NeuroneActivationCondition::FastGame,
+        ]);
+    }
+
+    #[test]
+    fn test_fitness_aggregation() {
+        let scores = vec![7, 1, 4, 10];
+        assert_eq!(FitnessAggregation::Mean.aggregate(&scores), 5.5);
+        assert_eq!(FitnessAggregation::Min.aggregate(&scores), 1.0);
+        assert_eq!(FitnessAggregation::Median.aggregate(&scores), 5.5);
+        assert_eq!(FitnessAggregation::Median.aggregate(&[3, 9, 1]), 3.0);
+        assert_eq!(FitnessAggregation::Percentile(0.0).aggregate(&scores), 1.0);
+        assert_eq!(FitnessAggregation::Percentile(25.0).aggregate(&scores), 1.0);
+        assert_eq!(FitnessAggregation::Percentile(50.0).aggregate(&scores), 4.0);
+        assert_eq!(FitnessAggregation::Percentile(100.0).aggregate(&scores), 10.0);
+        assert_eq!(FitnessAggregation::Mean.aggregate(&[]), 0.0);
+    }
+}
@@ -1,17 +1,229 @@
 use structopt::StructOpt;
+use structopt::clap::AppSettings;
+
+use crate::error::{Error, Result};
+use crate::params::GameParameters;
 
 #[derive(Debug, StructOpt)]
-#[structopt(name = "Chrome training mode", about = "A program to train a dinausor to play chrome dino game")]
+#[structopt(name = "Chrome training mode", about = "A program to train a dinausor to play chrome dino game", setting = AppSettings::SubcommandRequiredElseHelp)]
 pub struct ProgramArgs {
-    #[structopt(short = "t", long = "train", help = "Train the brain in the given folder", conflicts_with = "play_brain", conflicts_with = "play")]
-    pub folder_path: Option<String>,
+    #[structopt(subcommand)]
+    pub command : Command,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    #[structopt(about = "Play the game (space : jump/unbend, s : bend)")]
+    Play {
+        #[structopt(flatten)]
+        game : GameOptions,
+
+        #[structopt(long = "record", help = "Save the replay of the game in this file when the game is lost")]
+        record_path : Option<String>,
+    },
+
+    #[structopt(about = "Watch a brain of a brain file play the game ([b] in the game to play the next one)")]
+    Watch {
+        #[structopt(help = "the brain file")]
+        brain_path : String,
+
+        #[structopt(short = "i", long = "brain-index", default_value = "0", help = "the index of the brain played in the brain file")]
+        brain_index : usize,
+
+        #[structopt(short = "g", long = "ghosts", help = "play the other brains of the brain file at the same time as ghosts")]
+        ghosts : bool,
+
+        #[structopt(flatten)]
+        game : GameOptions,
+
+        #[structopt(long = "record", help = "Save the replay of the game in this file when the game is lost")]
+        record_path : Option<String>,
+    },
+
+    #[structopt(about = "Train the brains in the given folder (from new or continue from old)")]
+    Train {
+        #[structopt(help = "the training folder (a new folder in the result folder of the training config by default)")]
+        folder_path : Option<String>,
+
+        #[structopt(short = "o", long = "params", help = "get the params in this json file (they must be the same as the params of the folder to resume a training)")]
+        params_path : Option<String>,
+
+        #[structopt(short = "c", long = "training", help = "get the training config (budget, score limit...) in this json file")]
+        training_path : Option<String>,
+
+        #[structopt(short = "w", long = "workers", help = "the number of worker threads evaluating the brains (one per cpu by default)")]
+        nb_worker : Option<usize>,
+    },
+
+    #[structopt(about = "Evaluate the brains of a brain file without window on many seeds (score, survival time, cause of death)")]
+    Evaluate {
+        #[structopt(help = "the brain file (or a single brain json)")]
+        brain_path : String,
+
+        #[structopt(long = "seeds", help = "the land seeds of the evaluation")]
+        seeds : Vec<String>,
+
+        #[structopt(long = "seed-range", help = "the range of the numeric land seeds of the evaluation, like 0..100")]
+        seed_range : Option<String>,
+
+        #[structopt(long = "max-score", help = "stop a game at this score (no limit by default)")]
+        max_score : Option<u64>,
+
+        #[structopt(long = "max-ticks", default_value = "216000", help = "stop a game after this number of ticks, reported as capped (a brain that never dies stops, one hour at 60 fps by default)")]
+        max_tick : u64,
+
+        #[structopt(long = "json", help = "print the evaluation in json")]
+        json : bool,
+
+        #[structopt(flatten)]
+        game : GameOptions,
+    },
+
+    #[structopt(about = "Print the content of a brain file (energy and neurones of the brains) or of a training folder (generations)")]
+    Inspect {
+        #[structopt(help = "the brain file or the training folder")]
+        path : String,
+
+        #[structopt(flatten)]
+        game : GameOptions,
+    },
 
-    #[structopt(short = "b", long = "play_brain", help = "Play the game with the given brain", conflicts_with = "train", conflicts_with = "play")]
-    pub brain_path: Option<String>,
+    #[structopt(about = "Race all the brains of the given brain files at the same time on the same land")]
+    Race {
+        #[structopt(required = true, help = "the brain files")]
+        brain_paths : Vec<String>,
 
+        #[structopt(flatten)]
+        game : GameOptions,
+    },
+
+    #[structopt(about = "Play again a replay file (left/right : seek)")]
+    Replay {
+        #[structopt(help = "the replay file")]
+        replay_path : String,
+    },
+
+    #[structopt(about = "Show the dashboard of a training folder (curves per generation, play of the champions)")]
+    Dashboard {
+        #[structopt(help = "the training folder")]
+        folder_path : String,
+    },
+
+    #[structopt(about = "Edit a brain of a brain file by hand (created if it doesn't exist)")]
+    Edit {
+        #[structopt(help = "the brain file")]
+        brain_path : String,
+
+        #[structopt(short = "i", long = "brain-index", default_value = "0", help = "the index of the edited brain in the brain file (the other brains are kept on save)")]
+        brain_index : usize,
+
+        #[structopt(flatten)]
+        game : GameOptions,
+    },
+
+    #[structopt(about = "Manage the params files")]
+    Params(ParamsCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ParamsCommand {
+    #[structopt(about = "Write the default params (and training config) in a json file to edit it")]
+    Init {
+        #[structopt(default_value = "params.json", help = "the params file to create")]
+        params_path : String,
+
+        #[structopt(short = "c", long = "training", help = "also write the default training config in this json file")]
+        training_path : Option<String>,
+
+        #[structopt(short = "f", long = "force", help = "replace the files if they exist")]
+        force : bool,
+    },
+}
+
+// the options of the game shared by the subcommands
+// NOTE : not a doc comment, it would replace the about of the subcommands where it is flattened
+#[derive(Debug, StructOpt)]
+pub struct GameOptions {
     #[structopt(short = "o", long = "params", help = "get the params in this json file")]
-    pub params_path: Option<String>,
+    pub params_path : Option<String>,
+
+    #[structopt(long = "seed", help = "the land seed (replace the one of the params)")]
+    pub seed : Option<String>,
+
+    #[structopt(long = "fps", help = "the number of tick per second (replace the one of the params)")]
+    pub fps : Option<u16>,
+
+    #[structopt(long = "width", help = "the width of the game and its window (replace the one of the params)")]
+    pub width : Option<u16>,
+
+    #[structopt(long = "height", help = "the height of the game and its window (replace the one of the params)")]
+    pub height : Option<u16>,
+}
+
+impl GameOptions {
+    /// get the params of the file (or the default ones) with the values given in the options
+    pub fn get_params(&self) -> Result<GameParameters> {
+        let mut params = match &self.params_path {
+            Some(params_path) => GameParameters::new_from_file(params_path)?,
+            None => GameParameters::new_default(),
+        };
+        if let Some(seed) = &self.seed {
+            params.land_seed = seed.clone();
+        }
+        if let Some(fps) = self.fps {
+            params.game_fps = fps;
+        }
+        if let Some(width) = self.width {
+            params.game_width = width;
+        }
+        if let Some(height) = self.height {
+            params.game_height = height;
+        }
+        // the file is already checked, but the options can make it invalid (a game smaller than the neurones, no tick)
+        params.validate().map_err(|message| Error::InvalidOptions { message })?;
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subcommands() {
+        let args = ProgramArgs::from_iter_safe(["prog", "watch", "brain.json", "-i", "2", "--seed", "abc", "--fps", "30"]).unwrap();
+        match args.command {
+            Command::Watch { brain_path, brain_index, ghosts, game, record_path } => {
+                assert_eq!(brain_path, "brain.json");
+                assert_eq!(brain_index, 2);
+                assert!(!ghosts);
+                assert!(record_path.is_none());
+                let params = game.get_params().unwrap();
+                assert_eq!(params.land_seed, "abc");
+                assert_eq!(params.game_fps, 30);
+                assert_eq!(params.game_width, GameParameters::new_default().game_width);
+            },
+            command => panic!("not a watch command : {:?}", command),
+        }
+
+        // the options can't make the params invalid
+        for options in [["--fps", "0"], ["--width", "5"], ["--height", "3"]] {
+            let args = ProgramArgs::from_iter_safe(["prog", "play", options[0], options[1]]).unwrap();
+            match args.command {
+                Command::Play { game, .. } => assert!(matches!(game.get_params(), Err(Error::InvalidOptions { .. }))),
+                command => panic!("not a play command : {:?}", command),
+            }
+        }
+
+        // the number of workers is an option of the train command, not a param
+        let args = ProgramArgs::from_iter_safe(["prog", "train", "folder", "-w", "3"]).unwrap();
+        assert!(matches!(args.command, Command::Train { nb_worker : Some(3), .. }));
+
+        let args = ProgramArgs::from_iter_safe(["prog", "params", "init"]).unwrap();
+        assert!(matches!(args.command, Command::Params(ParamsCommand::Init { params_path, force : false, .. }) if params_path == "params.json"));
 
-    #[structopt(short = "p", long = "play", help = "Play the game", conflicts_with = "train", conflicts_with = "play_brain")]
-    pub play : bool,
-}
\ No newline at end of file
+        // the options of a subcommand are not in the others
+        assert!(ProgramArgs::from_iter_safe(["prog", "replay", "replay.json", "--seed", "abc"]).is_err());
+        assert!(ProgramArgs::from_iter_safe(["prog", "race"]).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+use iced::widget::canvas::{self, Cache, Cursor, Geometry, Path, Stroke, Text};
+use iced::theme::Theme;
+use iced::{Application, executor, Command, Rectangle, Size, Color, Point, Subscription, keyboard};
+
+use crate::brain::Brain;
+use crate::controller::Controller;
+use crate::entity::{Dinosaur, ObstacleEntityType};
+use crate::game::{Game, Playback, get_opposite, draw_obstacles, draw_hitbox_hint};
+use crate::params::GameParameters;
+use crate::sprite::{GameView, SpriteScene};
+
+/// a dinosaur of the race with its brain (or another controller)
+#[derive(Debug, Clone)]
+pub struct Racer<C = Brain> {
+    pub brain : C,
+    pub dinosaur : Dinosaur,
+    pub has_lost : bool,
+    /// the score of the land when the dinosaur died (or the current one)
+    pub score : u64,
+    /// the number of tick survived
+    pub nb_tick : u64,
+    /// the type of the obstacle that hit the dinosaur
+    pub death_cause : Option<ObstacleEntityType>,
+}
+
+/// many dinosaurs (one per brain) running on the same obstacles, each one dying on its own
+/// NOTE : the obstacles are simulated once for all the dinosaurs, each racer has the same result as a game played alone
+pub struct Race<C = Brain> {
+    /// the game of the obstacles (its own dinosaur isn't used)
+    pub land : Game,
+    pub racers : Vec<Racer<C>>,
+}
+
+impl<C : Controller> Race<C> {
+    pub fn new(params : &GameParameters, seed : &str, brains : Vec<C>) -> Self {
+        Self {
+            land : Game::new(params, seed, None, None),
+            racers : brains.into_iter().map(|brain| Racer {
+                brain,
+                dinosaur : Dinosaur::new_dinosaur(params, 0.0),
+                has_lost : false,
+                score : 0,
+                nb_tick : 0,
+                death_cause : None,
+            }).collect(),
+        }
+    }
+
+    /// the race is over when all the dinosaurs are dead
+    pub fn is_over(&self) -> bool {
+        self.racers.iter().all(|racer| racer.has_lost)
+    }
+
+    /// advance the race of one tick (do nothing if the race is over)
+    /// NOTE : same order as Game::update (move, collision, then the actions of the brain)
+    pub fn step(&mut self) {
+        if self.is_over() {
+            return;
+        }
+        let now = self.land.step_land();
+        let speed = self.land.get_speed();
+        for racer in self.racers.iter_mut().filter(|racer| !racer.has_lost) {
+            racer.dinosaur.update(now);
+            racer.score = self.land.score;
+            racer.nb_tick = self.land.tick;
+            if let Some(obstacle) = self.land.get_collision(&racer.dinosaur) {
+                racer.death_cause = Some(obstacle.type_.clone());
+                racer.has_lost = true;
+                continue;
+            }
+            racer.dinosaur.do_actions(racer.brain.get_activations(&self.land.obstacles, &racer.dinosaur, speed));
+        }
+    }
+
+    /// run the race until all the dinosaurs are dead (or the score of the land reach the max score)
+    /// WARN : without max score, a perfect brain never stops
+    pub fn run(&mut self, max_score : Option<u64>) {
+        while !self.is_over() && max_score.is_none_or(|max_score| self.land.score < max_score) {
+            self.step();
+        }
+    }
+
+    /// get the score of each racer (same order as the brains)
+    pub fn get_scores(&self) -> Vec<u64> {
+        self.racers.iter().map(|racer| racer.score).collect()
+    }
+}
+
+// ----------------- front -----------------
+
+/// the colors of the dinosaurs of the race (used in turn)
+const RACER_COLORS: [Color; 8] = [
+    Color { r: 0.9, g: 0.1, b: 0.1, a: 0.7 },
+    Color { r: 0.1, g: 0.6, b: 0.1, a: 0.7 },
+    Color { r: 0.1, g: 0.2, b: 0.9, a: 0.7 },
+    Color { r: 0.9, g: 0.6, b: 0.0, a: 0.7 },
+    Color { r: 0.6, g: 0.1, b: 0.8, a: 0.7 },
+    Color { r: 0.0, g: 0.7, b: 0.7, a: 0.7 },
+    Color { r: 0.9, g: 0.3, b: 0.6, a: 0.7 },
+    Color { r: 0.4, g: 0.3, b: 0.2, a: 0.7 },
+];
+
+/// the number of racer shown in the ranking
+const NB_RANKING_LINE: usize = 10;
+
+/// get the names of the racers in the ranking (their index in the brain files)
+pub fn get_brain_names(nb_brain : usize) -> Vec<String> {
+    (0..nb_brain).map(|i| format!("brain {}", i)).collect()
+}
+
+/// get the racers to watch a brain of a brain file : the watched one first (marked in the ranking), then the ghosts (like BrainSelection)
+/// WARN : the index must be in the brains
+pub fn get_watched_racers<C : Clone>(brains : &[C], index : usize, with_ghosts : bool) -> (Vec<C>, Vec<String>) {
+    let mut order = vec![index];
+    if with_ghosts {
+        order.extend((0..brains.len()).filter(|i| *i != index));
+    }
+    let names = order.iter()
+        .map(|i| if *i == index { format!("brain {} (watched)", i) } else { format!("brain {}", i) })
+        .collect();
+
+    (order.iter().map(|i| brains[*i].clone()).collect(), names)
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Update,
+    Restart,
+    TogglePause,
+    /// advance the race of one tick
+    Step,
+    /// multiply the speed (clamped between 0.25 and 8)
+    ChangeSpeed(f64),
+    ToggleHitbox,
+}
+
+/// the race drawn in a window (of neurone webs or of other controllers)
+pub struct RaceView<C = Brain> {
+    race : Race<C>,
+    params : GameParameters,
+    cache : Cache,
+    playback : Playback,
+    /// draw the hitboxes instead of the sprites (to debug)
+    show_hitbox : bool,
+    /// the name of each racer in the ranking (same order as the racers)
+    names : Vec<String>,
+}
+
+impl<C : Controller> RaceView<C> {
+    /// get the lines of the ranking (alive racers first, then by score)
+    fn get_ranking_lines(&self) -> Vec<String> {
+        let mut ranking : Vec<(usize, &Racer<C>)> = self.race.racers.iter().enumerate().collect();
+        ranking.sort_by(|(_, a), (_, b)| a.has_lost.cmp(&b.has_lost).then(b.nb_tick.cmp(&a.nb_tick)));
+        ranking.into_iter()
+            .take(NB_RANKING_LINE)
+            .enumerate()
+            .map(|(rank, (i, racer))| match &racer.death_cause {
+                Some(death_cause) => format!("{}. {} : {} (dead by {})", rank + 1, self.names[i], racer.score, death_cause),
+                None => format!("{}. {} : {}", rank + 1, self.names[i], racer.score),
+            })
+            .collect()
+    }
+}
+
+impl<C : Controller + 'static> Application for RaceView<C> {
+    type Theme = Theme;
+    type Executor = executor::Default;
+    type Message = Message;
+    /// the params, the brains and their names in the ranking (see get_brain_names)
+    type Flags = (GameParameters, Vec<C>, Vec<String>);
+
+    fn new(flags: Self::Flags) -> (Self, Command<Self::Message>) {
+        let (params, brains, names) = flags;
+        (
+            Self {
+                race : Race::new(&params, params.land_seed.as_str(), brains),
+                params,
+                cache : Cache::default(),
+                playback : Playback::default(),
+                show_hitbox : false,
+                names,
+            },
+            Command::none(),
+        )
+    }
+
+    fn title(&self) -> String {
+        String::from("Chrome Dinosaur - Race")
+    }
+
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        match message {
+            Message::Update => {
+                if self.playback.is_paused() {
+                    return Command::none();
+                }
+                for _ in 0..self.playback.next_nb_tick() {
+                    self.race.step();
+                }
+            },
+            Message::Restart => {
+                let brains = self.race.racers.drain(..).map(|racer| racer.brain).collect();
+                self.race = Race::new(&self.params, self.params.land_seed.as_str(), brains);
+            },
+            Message::TogglePause => self.playback.toggle_pause(),
+            Message::Step => self.race.step(),
+            Message::ChangeSpeed(factor) => self.playback.change_speed(factor),
+            Message::ToggleHitbox => self.show_hitbox = !self.show_hitbox,
+        }
+        self.cache.clear();
+        Command::none()
+    }
+
+    fn view(&self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
+        GameView::new(self).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        iced::time::every(std::time::Duration::from_nanos(1_000_000_000/self.params.game_fps as u64)).map(|_| {
+            Message::Update
+        })
+    }
+}
+
+impl<C : Controller> canvas::Program<Message> for RaceView<C> {
+    type State = ();
+
+    fn update(
+            &self,
+            _state: &mut Self::State,
+            event: canvas::Event,
+            _bounds: Rectangle,
+            _cursor: Cursor,
+        ) -> (canvas::event::Status, Option<Message>) {
+        let message = match event {
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived(' ')) if self.race.is_over() => Some(Message::Restart),
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('p')) => Some(Message::TogglePause),
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('n')) => Some(Message::Step),
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('+')) => Some(Message::ChangeSpeed(2.0)),
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('-')) => Some(Message::ChangeSpeed(0.5)),
+            canvas::Event::Keyboard(keyboard::Event::CharacterReceived('h')) => Some(Message::ToggleHitbox),
+            _ => None,
+        };
+        match message {
+            Some(message) => (canvas::event::Status::Captured, Some(message)),
+            None => (canvas::event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let geometry = self.cache.draw(bounds.size(), |frame| {
+            let land = &self.race.land;
+            draw_obstacles(frame, &self.params, &land.obstacles, self.show_hitbox);
+
+            // the dinosaurs still alive, with the color of their brain (a frame around the sprite, or the filled hitbox)
+            for (i, racer) in self.race.racers.iter().enumerate().filter(|(_, racer)| !racer.has_lost) {
+                let position = get_opposite(&self.params, Point { x: racer.dinosaur.x as f32, y: racer.dinosaur.y as f32 }, racer.dinosaur.height as f32);
+                let size = Size { width: racer.dinosaur.width as f32, height: racer.dinosaur.height as f32 };
+                let color = RACER_COLORS[i % RACER_COLORS.len()];
+                if self.show_hitbox {
+                    frame.fill_rectangle(position, size, color);
+                }else{
+                    frame.stroke(&Path::rectangle(position, size), Stroke::default().with_color(color).with_width(2.0));
+                }
+            }
+
+            let nb_alive = self.race.racers.iter().filter(|racer| !racer.has_lost).count();
+            let mut lines = vec![
+                format!("Score : {}", land.score),
+                format!("Time : {:.1} s", land.get_tick_time(land.tick)),
+                format!("Land seed : {}", land.land_seed),
+                format!("Alive : {}/{}{}", nb_alive, self.race.racers.len(), if self.race.is_over() { " (press space to restart)" } else { "" }),
+            ];
+            lines.extend(self.get_ranking_lines());
+            for (i, line) in lines.into_iter().enumerate() {
+                frame.fill_text(Text {
+                    content: line,
+                    position: Point { x: 0.0, y: i as f32 * 25.0 },
+                    size: 20.0,
+                    color: Color::BLACK,
+                    ..Text::default()
+                });
+            }
+
+            frame.fill_text(Text {
+                content: self.playback.get_status(),
+                position: Point { x: 700.0, y: 0.0 },
+                size: 20.0,
+                color: Color::BLACK,
+                ..Text::default()
+            });
+            draw_hitbox_hint(frame);
+        });
+
+        vec![geometry]
+    }
+}
+
+impl<C : Controller> SpriteScene<Message> for RaceView<C> {
+    fn get_land(&self) -> &Game {
+        &self.race.land
+    }
+
+    fn get_sprite_dinosaurs(&self) -> Vec<&Dinosaur> {
+        self.race.racers.iter().filter(|racer| !racer.has_lost).map(|racer| &racer.dinosaur).collect()
+    }
+
+    fn hide_sprites(&self) -> bool {
+        self.show_hitbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::utils::str_to_u8_array;
+
+    use super::*;
+
+    #[test]
+    fn test_race_is_exact() {
+        // each racer must have the same result as a game played alone
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brains : Vec<Brain> = (0..8).map(|_| Brain::new(&params, &mut rng)).collect();
+
+        let mut race = Race::new(&params, "race", brains.clone());
+        race.run(None);
+        assert!(race.is_over());
+
+        for (racer, brain) in race.racers.iter().zip(brains) {
+            let mut game = Game::new(&params, "race", Some(brain), None);
+            while !game.has_lost {
+                game.step();
+            }
+            assert_eq!(racer.score, game.score);
+            assert_eq!(racer.nb_tick, game.tick);
+            assert_eq!(racer.dinosaur, game.dinosaur);
+            assert_eq!(racer.death_cause, game.death_cause);
+        }
+    }
+
+    #[test]
+    fn test_watched_racers() {
+        let brains = vec!["a", "b", "c"];
+        assert_eq!(get_watched_racers(&brains, 1, false), (vec!["b"], vec!["brain 1 (watched)".to_string()]));
+        let (racers, names) = get_watched_racers(&brains, 1, true);
+        assert_eq!(racers, vec!["b", "a", "c"]);
+        assert_eq!(names, vec!["brain 1 (watched)".to_string(), "brain 0".to_string(), "brain 2".to_string()]);
+        assert_eq!(get_brain_names(2), vec!["brain 0".to_string(), "brain 1".to_string()]);
+    }
+
+    #[test]
+    fn test_race_max_score() {
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let mut race = Race::new(&params, "race", vec![Brain::new(&params, &mut rng)]);
+        race.run(Some(0));
+        assert_eq!(race.land.tick, 0);
+        assert_eq!(race.get_scores(), vec![0]);
+    }
+}
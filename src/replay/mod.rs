@@ -0,0 +1,133 @@
+use std::fs;
+
+use iced::widget::canvas::Cache;
+use serde::{Serialize, Deserialize};
+
+use crate::brain::Brain;
+use crate::error::{Error, Result};
+use crate::game::Game;
+use crate::neurone::NeuroneWebAction;
+use crate::params::GameParameters;
+use crate::utils::write_json;
+
+/// an action sent to the game by the player (Message::Do), done after the tick
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct ReplayAction {
+    pub tick : u64,
+    pub action : NeuroneWebAction,
+}
+
+/// a recorded game, everything needed to simulate it again exactly
+/// NOTE : the actions of the brain are not saved, they are computed again by the simulation
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Replay {
+    pub params : GameParameters,
+    pub land_seed : String,
+    pub brain : Option<Brain>,
+    /// the actions of the player, sorted by tick
+    pub actions : Vec<ReplayAction>,
+
+    /// the number of tick of the recorded game
+    pub nb_tick : u64,
+    /// the final score of the recorded game
+    pub score : u64,
+}
+
+impl Replay {
+    /// load a replay file
+    pub fn new_from_file(path : &str) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|source| Error::Read { path : path.to_string(), source })?;
+        serde_json::from_str(&content).map_err(|source| Error::CorruptReplay { path : path.to_string(), source })
+    }
+
+    /// save the replay in a json file
+    pub fn save(&self, path : &str) -> Result<()> {
+        write_json(path, self)
+    }
+
+    /// get the game at the beginning of the replay
+    pub fn get_game(&self, cache : Option<Cache>) -> Game {
+        Game::new(&self.params, &self.land_seed, self.brain.clone(), cache)
+    }
+
+    /// do the actions of the player recorded at the current tick of the game
+    pub fn do_actions(&self, game : &mut Game) {
+        let tick = game.tick;
+        let begin = self.actions.partition_point(|action| action.tick < tick);
+        for action in self.actions[begin..].iter().take_while(|action| action.tick == tick) {
+            game.do_player_action(&action.action);
+        }
+    }
+
+    /// advance the game of one tick of the replay
+    pub fn step(&self, game : &mut Game) {
+        self.do_actions(game);
+        game.step();
+    }
+
+    /// simulate the replay from the beginning to the tick (or until the game is lost)
+    pub fn simulate_to(&self, tick : u64, cache : Option<Cache>) -> Game {
+        let mut game = self.get_game(cache);
+        while game.tick < tick && !game.has_lost {
+            self.step(&mut game);
+        }
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::utils::{str_to_u8_array, get_test_path};
+
+    use super::*;
+
+    #[test]
+    fn test_replay_is_exact() {
+        // a game with a brain and some actions of the player must be simulated again exactly
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let mut game = Game::new(&params, "replay", Some(Brain::new(&params, &mut rng)), None);
+        while !game.has_lost {
+            match game.tick {
+                30 | 150 => {
+                    game.do_player_action(&NeuroneWebAction::Jump);
+                },
+                80 => {
+                    game.do_player_action(&NeuroneWebAction::Bend);
+                    game.do_player_action(&NeuroneWebAction::Jump);
+                },
+                90 => {
+                    game.do_player_action(&NeuroneWebAction::Unbend);
+                },
+                _ => {},
+            }
+            game.step();
+        }
+
+        let replay = game.to_replay();
+        assert_eq!(replay.nb_tick, game.tick);
+        assert_eq!(replay.actions.len(), 5);
+
+        // through a file
+        let path = get_test_path("replay.json");
+        replay.save(&path).unwrap();
+        let loaded = Replay::new_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, replay);
+
+        let replayed = loaded.simulate_to(u64::MAX, None);
+        assert!(replayed.has_lost);
+        assert_eq!(replayed.tick, game.tick);
+        assert_eq!(replayed.score, game.score);
+        assert_eq!(replayed.dinosaur, game.dinosaur);
+        assert_eq!(replayed.obstacles, game.obstacles);
+
+        // seek in the middle
+        let middle = loaded.simulate_to(100, None);
+        assert_eq!(middle.tick, 100);
+        assert_eq!(middle.actions.len(), 4);
+    }
+}
@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+
+use rand::Rng;
+use rand_pcg::Pcg64;
+use serde::{Serialize, Deserialize};
+
+use crate::{brain::{Brain, BrainScore}, controller::Controller, params::{GameParameters, TrainingConfig}};
+
+/// the selection strategy used by the training (choose in the params json)
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub enum SelectionType {
+    /// keep every brain tied at the best score (tie break on the energie if the limit score is reached)
+    #[default]
+    TopTies,
+    /// nb_selected tournaments between size random brains
+    Tournament { size : u64, nb_selected : u64 },
+    /// keep the percent (between 0 and 100) best brains
+    Truncation { percent : f64 },
+    /// draw nb_selected brains with a probability proportional to their score
+    Roulette { nb_selected : u64 },
+    /// draw nb_selected brains with a probability proportional to their rank (the worst has rank 1)
+    Rank { nb_selected : u64 },
+}
+
+impl SelectionType {
+    /// get the selection strategy of this type
+    pub fn get_selection<C : Controller>(&self, training : &TrainingConfig) -> Box<dyn Selection<C>> {
+        match self {
+            SelectionType::TopTies => Box::new(TopTiesSelection { limit_score : training.limit_score }),
+            SelectionType::Tournament { size, nb_selected } => Box::new(TournamentSelection { size : *size, nb_selected : *nb_selected }),
+            SelectionType::Truncation { percent } => Box::new(TruncationSelection { percent : *percent }),
+            SelectionType::Roulette { nb_selected } => Box::new(RouletteSelection { nb_selected : *nb_selected }),
+            SelectionType::Rank { nb_selected } => Box::new(RankSelection { nb_selected : *nb_selected }),
+        }
+    }
+}
+
+/// a way to choose the parents of the next generation
+pub trait Selection<C = Brain> {
+    /// select the parents among the scored brains (can't be empty)
+    /// NOTE : the parents are sorted from the best to the worst (see compare_brain_score)
+    fn select<'a>(&self, params : &GameParameters, scores : &'a [BrainScore<C>], rng : &mut Pcg64) -> Vec<&'a BrainScore<C>>;
+}
+
+/// order two scored brains : the best score first, then the lowest energie
+pub fn compare_brain_score<C>(a : &BrainScore<C>, b : &BrainScore<C>) -> Ordering {
+    b.score.total_cmp(&a.score)
+        .then_with(|| a.energy.total_cmp(&b.energy))
+}
+
+/// sort the selected brains from the best to the worst
+fn sort_selected<C>(mut selected : Vec<&BrainScore<C>>) -> Vec<&BrainScore<C>> {
+    selected.sort_by(|a, b| compare_brain_score(a, b));
+    selected
+}
+
+/// remove the brains selected several times (the selections with replacement), keep the order
+/// NOTE : the duplicates are kept as parents (a brain selected twice has twice more children),
+/// but not as old best brains or in the brain files
+pub fn dedup_selected<'a, C : PartialEq>(selected : &[&'a BrainScore<C>]) -> Vec<&'a BrainScore<C>> {
+    let mut unique : Vec<&BrainScore<C>> = Vec::new();
+    for score in selected {
+        if !unique.iter().any(|kept| kept.energy == score.energy && kept.brain == score.brain) {
+            unique.push(score);
+        }
+    }
+
+    unique
+}
+
+/// get the index of each selected brain among the deduplicated ones (see dedup_selected)
+pub fn get_selected_indexes<C : PartialEq>(selected : &[&BrainScore<C>], unique : &[&BrainScore<C>]) -> Vec<usize> {
+    selected.iter()
+        .map(|score| unique.iter()
+            .position(|kept| kept.energy == score.energy && kept.brain == score.brain)
+            .expect("A selected brain is missing from the deduplicated brains"))
+        .collect()
+}
+
+/// draw nb_selected brains with a probability proportional to their weight
+/// NOTE : if all the weights are null, the draw is uniform
+fn weighted_draw<'a, C>(candidates : &[&'a BrainScore<C>], weights : &[f64], nb_selected : u64, rng : &mut Pcg64) -> Vec<&'a BrainScore<C>> {
+    let total : f64 = weights.iter().sum();
+    let mut selected = Vec::new();
+    for _ in 0..nb_selected.max(1) {
+        if total <= 0.0 {
+            selected.push(candidates[rng.gen_range(0..candidates.len())]);
+            continue;
+        }
+        let mut target = rng.gen_range(0.0..total);
+        let mut chosen = candidates.len() - 1;
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                chosen = i;
+                break;
+            }
+            target -= weight;
+        }
+        selected.push(candidates[chosen]);
+    }
+
+    selected
+}
+
+/// keep every brain tied at the best score, break the tie on the energie if the limit score is reached
+pub struct TopTiesSelection {
+    pub limit_score : u64,
+}
+
+impl<C : Controller> Selection<C> for TopTiesSelection {
+    fn select<'a>(&self, _params : &GameParameters, scores : &'a [BrainScore<C>], _rng : &mut Pcg64) -> Vec<&'a BrainScore<C>> {
+        let mut best_brains_score : Vec<&BrainScore<C>> = vec![&scores[0]];
+        for score in scores {
+            if score.score > best_brains_score[0].score {
+                best_brains_score = vec![score];
+            }else if score.score == best_brains_score[0].score {
+                best_brains_score.push(score);
+            }
+        }
+
+        // sort on the energie if the score is max
+        if best_brains_score[0].score == self.limit_score as f64 {
+            let mut best_brains = vec![best_brains_score[0]];
+            for score in &best_brains_score {
+                if score.energy < best_brains[0].energy {
+                    best_brains = vec![score];
+                }else if score.energy == best_brains[0].energy {
+                    best_brains.push(score);
+                }
+            }
+            best_brains
+        }else{
+            best_brains_score
+        }
+    }
+}
+
+/// nb_selected tournaments, each one between size random brains (with replacement)
+pub struct TournamentSelection {
+    pub size : u64,
+    pub nb_selected : u64,
+}
+
+impl<C : Controller> Selection<C> for TournamentSelection {
+    fn select<'a>(&self, _params : &GameParameters, scores : &'a [BrainScore<C>], rng : &mut Pcg64) -> Vec<&'a BrainScore<C>> {
+        let mut selected = Vec::new();
+        for _ in 0..self.nb_selected.max(1) {
+            let mut winner = &scores[rng.gen_range(0..scores.len())];
+            for _ in 1..self.size {
+                let challenger = &scores[rng.gen_range(0..scores.len())];
+                if compare_brain_score(challenger, winner) == Ordering::Less {
+                    winner = challenger;
+                }
+            }
+            selected.push(winner);
+        }
+
+        sort_selected(selected)
+    }
+}
+
+/// keep the percent best brains (at least one)
+pub struct TruncationSelection {
+    pub percent : f64,
+}
+
+impl<C : Controller> Selection<C> for TruncationSelection {
+    fn select<'a>(&self, _params : &GameParameters, scores : &'a [BrainScore<C>], _rng : &mut Pcg64) -> Vec<&'a BrainScore<C>> {
+        let nb_selected = ((self.percent.clamp(0.0, 100.0) / 100.0) * scores.len() as f64).ceil() as usize;
+        let mut selected = sort_selected(scores.iter().collect());
+        selected.truncate(nb_selected.max(1));
+        selected
+    }
+}
+
+/// draw the brains with a probability proportional to their score
+pub struct RouletteSelection {
+    pub nb_selected : u64,
+}
+
+impl<C : Controller> Selection<C> for RouletteSelection {
+    fn select<'a>(&self, _params : &GameParameters, scores : &'a [BrainScore<C>], rng : &mut Pcg64) -> Vec<&'a BrainScore<C>> {
+        let candidates : Vec<&BrainScore<C>> = scores.iter().collect();
+        let weights : Vec<f64> = scores.iter().map(|score| score.score.max(0.0)).collect();
+        sort_selected(weighted_draw(&candidates, &weights, self.nb_selected, rng))
+    }
+}
+
+/// draw the brains with a probability proportional to their rank (the worst has rank 1)
+pub struct RankSelection {
+    pub nb_selected : u64,
+}
+
+impl<C : Controller> Selection<C> for RankSelection {
+    fn select<'a>(&self, _params : &GameParameters, scores : &'a [BrainScore<C>], rng : &mut Pcg64) -> Vec<&'a BrainScore<C>> {
+        let candidates = sort_selected(scores.iter().collect());
+        let weights : Vec<f64> = (0..candidates.len()).map(|i| (candidates.len() - i) as f64).collect();
+        sort_selected(weighted_draw(&candidates, &weights, self.nb_selected, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_top_ties_selection() {
+        let params = GameParameters::new_default();
+        let scores = BrainScore::new_random_tests(&params, &[3.0, 8.0, 1.0, 8.0, 5.0]);
+        let mut rng = Pcg64::from_seed([0; 32]);
+        let selected = SelectionType::TopTies.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert_eq!(selected.len(), 2);
+        assert!(selected.iter().all(|score| score.score == 8.0));
+    }
+
+    #[test]
+    fn test_truncation_selection() {
+        let params = GameParameters::new_default();
+        let scores = BrainScore::new_random_tests(&params, &[3.0, 8.0, 1.0, 7.0, 5.0]);
+        let mut rng = Pcg64::from_seed([0; 32]);
+        let selected = SelectionType::Truncation { percent : 40.0 }.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert_eq!(selected.iter().map(|score| score.score).collect::<Vec<f64>>(), vec![8.0, 7.0]);
+
+        // at least one brain is kept
+        let selected = SelectionType::Truncation { percent : 0.0 }.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].score, 8.0);
+    }
+
+    #[test]
+    fn test_tournament_selection() {
+        let params = GameParameters::new_default();
+        let scores = BrainScore::new_random_tests(&params, &[3.0, 8.0, 1.0, 7.0, 5.0]);
+        let mut rng = Pcg64::from_seed([0; 32]);
+        let selected = SelectionType::Tournament { size : 3, nb_selected : 10 }.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert_eq!(selected.len(), 10);
+        // sorted from the best to the worst
+        assert!(selected.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        // a big tournament (with replacement) nearly always contains the best brain
+        let selected = SelectionType::Tournament { size : 50, nb_selected : 5 }.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert!(selected.iter().all(|score| score.score == 8.0));
+    }
+
+    #[test]
+    fn test_roulette_and_rank_selection() {
+        let params = GameParameters::new_default();
+        let scores = BrainScore::new_random_tests(&params, &[0.0, 10.0, 0.0]);
+        let mut rng = Pcg64::from_seed([0; 32]);
+        // the null scores can't be drawn by the roulette
+        let selected = SelectionType::Roulette { nb_selected : 20 }.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert_eq!(selected.len(), 20);
+        assert!(selected.iter().all(|score| score.score == 10.0));
+
+        // the rank selection keep a chance for the worst brains
+        let selected = SelectionType::Rank { nb_selected : 200 }.get_selection(&TrainingConfig::new_default()).select(&params, &scores, &mut rng);
+        assert_eq!(selected.len(), 200);
+        assert!(selected.iter().any(|score| score.score == 0.0));
+        assert!(selected.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    }
+
+    #[test]
+    fn test_dedup_selected() {
+        let params = GameParameters::new_default();
+        let scores = BrainScore::new_random_tests(&params, &[3.0, 8.0, 1.0]);
+        // the brains drawn several times are only kept once, in the order of the selection
+        let selected = vec![&scores[1], &scores[1], &scores[0], &scores[1]];
+        assert_eq!(dedup_selected(&selected), vec![&scores[1], &scores[0]]);
+    }
+}
@@ -0,0 +1,263 @@
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::brain::{Brain, BrainScore};
+use crate::controller::Controller;
+use crate::params::GameParameters;
+use crate::selection::{Selection, compare_brain_score, get_selected_indexes};
+
+/// the number of species detailed in the training output (the biggest ones)
+const NB_SUMMARY_SPECIES: usize = 10;
+
+/// a group of similar brains (see Controller::get_distance)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Species<C = Brain> {
+    pub id : u64,
+    /// the brain the others are compared to (the best brain of the species at the last generation)
+    pub representative : C,
+    /// the best score ever reached by the species
+    pub best_score : f64,
+    /// the number of generations since the best score of the species improved
+    pub stagnation : u64,
+    /// the number of brains of the species at the last generation
+    pub size : usize,
+}
+
+/// the parents chosen in a species, with the number of children they have in the next generation
+#[derive(Debug, Clone)]
+pub struct SpeciesParents<C = Brain> {
+    pub parents : Vec<BrainScore<C>>,
+    pub nb_children : u64,
+}
+
+impl<C : PartialEq> SpeciesParents<C> {
+    /// get the parents to save in a brain file (index among the saved brains)
+    pub fn to_saved(&self, saved_brains : &[&BrainScore<C>]) -> SavedSpeciesParents {
+        let parents : Vec<&BrainScore<C>> = self.parents.iter().collect();
+        SavedSpeciesParents {
+            parents : get_selected_indexes(&parents, saved_brains),
+            nb_children : self.nb_children,
+        }
+    }
+}
+
+/// the parents of a species in a brain file (index in the brains of the file)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSpeciesParents {
+    pub parents : Vec<usize>,
+    pub nb_children : u64,
+}
+
+impl SavedSpeciesParents {
+    /// get the parents back from the saved brains
+    pub fn to_species_parents<C : Clone>(&self, saved_brains : &[BrainScore<C>]) -> SpeciesParents<C> {
+        SpeciesParents {
+            parents : self.parents.iter().map(|i| saved_brains[*i].clone()).collect(),
+            nb_children : self.nb_children,
+        }
+    }
+}
+
+/// the species of a training (NEAT-style), kept from one generation to the next
+/// NOTE : the species are saved in the brain files (with the parents of each species) to resume the training exactly,
+/// a brain file without species (an old one) is speciated again : the ids and the stagnation of the species restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Speciation<C = Brain> {
+    /// the compatibility distance under which a brain joins a species
+    pub threshold : f64,
+    pub species : Vec<Species<C>>,
+    next_id : u64,
+}
+
+impl<C : Controller> Speciation<C> {
+    pub fn new(threshold : f64) -> Self {
+        Self {
+            threshold,
+            species : Vec::new(),
+            next_id : 0,
+        }
+    }
+
+    /// put each brain in the first species whose representative is close enough (or in a new species)
+    /// return the index of the species of each brain
+    fn assign(&mut self, params : &GameParameters, scores : &[BrainScore<C>]) -> Vec<usize> {
+        let mut assignment = Vec::new();
+        for score in scores {
+            let index = self.species.iter().position(|species| species.representative.get_distance(&score.brain, params) < self.threshold);
+            let index = match index {
+                Some(index) => index,
+                None => {
+                    self.species.push(Species {
+                        id : self.next_id,
+                        representative : score.brain.clone(),
+                        best_score : f64::NEG_INFINITY,
+                        stagnation : 0,
+                        size : 0,
+                    });
+                    self.next_id += 1;
+                    self.species.len() - 1
+                },
+            };
+            assignment.push(index);
+        }
+
+        assignment
+    }
+
+    /// speciate the scored brains and choose the parents of the next generation in each species
+    /// NOTE : the children are shared between the species in proportion of their mean score (fitness sharing),
+    /// so a new web gets some generations to improve before competing with the best brains
+    /// NOTE : the empty species and the stagnant ones are removed (except the species of the best brain)
+    pub fn select(&mut self, params : &GameParameters, selection : &dyn Selection<C>, scores : &[BrainScore<C>], rng : &mut Pcg64) -> Vec<SpeciesParents<C>> {
+        let assignment = self.assign(params, scores);
+        let mut members : Vec<Vec<BrainScore<C>>> = vec![Vec::new(); self.species.len()];
+        for (score, index) in scores.iter().zip(&assignment) {
+            members[*index].push(score.clone());
+        }
+        let best_species = scores.iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| compare_brain_score(a, b))
+            .map(|(i, _)| assignment[i]);
+
+        // update the species with their new members
+        let mut kept = Vec::new();
+        for (i, (mut species, members)) in self.species.drain(..).zip(members).enumerate() {
+            let Some(best) = members.iter().min_by(|a, b| compare_brain_score(a, b)) else {
+                continue;
+            };
+            if best.score > species.best_score {
+                species.best_score = best.score;
+                species.stagnation = 0;
+            }else{
+                species.stagnation += 1;
+            }
+            species.representative = best.brain.clone();
+            species.size = members.len();
+            if species.stagnation >= params.speciation_stagnation_limit && Some(i) != best_species {
+                continue;
+            }
+            kept.push((species, members));
+        }
+
+        // the fitness of a species is the mean score of its brains (each score is shared by the brains of the species)
+        let fitness : Vec<f64> = kept.iter()
+            .map(|(_, members)| members.iter().map(|score| score.score).sum::<f64>() / members.len() as f64)
+            .collect();
+        let nb_children = share_children(params.training_nb_brain, &fitness);
+
+        let mut species_parents = Vec::new();
+        for ((species, members), nb_children) in kept.into_iter().zip(nb_children) {
+            species_parents.push(SpeciesParents {
+                parents : selection.select(params, &members, rng).into_iter().cloned().collect(),
+                nb_children,
+            });
+            self.species.push(species);
+        }
+
+        species_parents
+    }
+
+    /// get the description of the species for the training output (id and size of the biggest species)
+    pub fn get_summary(&self) -> String {
+        let mut species : Vec<&Species<C>> = self.species.iter().collect();
+        species.sort_by_key(|species| std::cmp::Reverse(species.size));
+        let mut sizes : Vec<String> = species.iter()
+            .take(NB_SUMMARY_SPECIES)
+            .map(|species| format!("#{} : {}", species.id, species.size))
+            .collect();
+        if species.len() > NB_SUMMARY_SPECIES {
+            sizes.push("...".to_string());
+        }
+        format!("{} species ({})", self.species.len(), sizes.join(", "))
+    }
+}
+
+/// share the children between the species in proportion of their fitness (the rest of the rounding goes to the best ones)
+/// NOTE : if all the fitness are null, the children are shared equally
+fn share_children(nb_children : u64, fitness : &[f64]) -> Vec<u64> {
+    if fitness.is_empty() {
+        return Vec::new();
+    }
+    let total : f64 = fitness.iter().sum();
+    let shares : Vec<f64> = if total > 0.0 {
+        fitness.iter().map(|fitness| fitness / total).collect()
+    }else{
+        vec![1.0 / fitness.len() as f64; fitness.len()]
+    };
+    let mut children : Vec<u64> = shares.iter().map(|share| (share * nb_children as f64).floor() as u64).collect();
+
+    let rest = nb_children.saturating_sub(children.iter().sum());
+    let mut order : Vec<usize> = (0..shares.len()).collect();
+    order.sort_by(|a, b| shares[*b].total_cmp(&shares[*a]));
+    for i in order.into_iter().cycle().take(rest as usize) {
+        children[i] += 1;
+    }
+
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::neurone::NeuroneWeb;
+    use crate::params::TrainingConfig;
+    use crate::selection::SelectionType;
+    use crate::utils::str_to_u8_array;
+
+    use super::*;
+
+    #[test]
+    fn test_share_children() {
+        assert_eq!(share_children(10, &[3.0, 1.0, 1.0]), vec![6, 2, 2]);
+        assert_eq!(share_children(10, &[1.0, 1.0, 1.0]), vec![4, 3, 3]);
+        assert_eq!(share_children(5, &[0.0, 0.0]), vec![3, 2]);
+        assert!(share_children(5, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_brain_distance() {
+        let params = GameParameters::new_default();
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+        let brain = Brain::new(&params, &mut rng);
+        assert_eq!(brain.get_distance(&brain, &params), 0.0);
+
+        // a new web is a full difference
+        let mut bigger = brain.clone();
+        bigger.neurone_web.push(NeuroneWeb::new_random(&params, &mut rng));
+        let distance = brain.get_distance(&bigger, &params);
+        assert_eq!(distance, bigger.get_distance(&brain, &params));
+        assert_eq!(distance, params.speciation_web_coefficient / bigger.neurone_web.len() as f64);
+    }
+
+    #[test]
+    fn test_speciation() {
+        let mut params = GameParameters::new_default();
+        params.training_nb_brain = 10;
+        params.speciation_stagnation_limit = 2;
+        let training = TrainingConfig::new_default();
+        let selection = SelectionType::Truncation { percent : 50.0 }.get_selection(&training);
+        let mut rng = Pcg64::from_seed(str_to_u8_array(params.brain_seed.as_str()));
+
+        // two groups of clones : one species per group
+        let brain_a = Brain::new(&params, &mut rng);
+        let brain_b = Brain { neurone_web : Vec::new() };
+        let score = |brain : &Brain, score : f64| BrainScore::new_test(&params, brain.clone(), score);
+        let scores = vec![score(&brain_a, 6.0), score(&brain_b, 1.0), score(&brain_a, 6.0), score(&brain_b, 3.0)];
+
+        let mut speciation = Speciation::new(0.5);
+        let species_parents = speciation.select(&params, &*selection, &scores, &mut rng);
+        assert_eq!(speciation.species.len(), 2);
+        assert_eq!(speciation.get_summary(), "2 species (#0 : 2, #1 : 2)");
+        // the mean scores are 6 and 2
+        assert_eq!(species_parents.iter().map(|parents| parents.nb_children).collect::<Vec<u64>>(), vec![8, 2]);
+        assert_eq!(species_parents[1].parents[0].score, 3.0);
+
+        // the species of the brain b stagnates and is removed, not the one of the best brain
+        for _ in 0..2 {
+            speciation.select(&params, &*selection, &scores, &mut rng);
+        }
+        assert_eq!(speciation.species.len(), 1);
+        assert_eq!(speciation.species[0].id, 0);
+    }
+}
@@ -0,0 +1,214 @@
+use iced::widget::canvas::{self, Cursor, Geometry};
+use iced::widget::image::Handle;
+use iced::{Element, Length, Point, Rectangle, Size, Theme, Vector};
+use iced_graphics::Primitive;
+use iced_native::image::Renderer as _;
+use iced_native::layout::{self, Layout};
+use iced_native::renderer::{self, Renderer as _};
+use iced_native::widget::{Tree, Widget};
+use iced_native::{event, Clipboard, Event, Shell};
+
+use crate::entity::{Dinosaur, ObstacleEntityType};
+use crate::game::{Game, get_opposite};
+
+/// the folder of the sprites (in the crate, whatever the working directory)
+const SPRITE_FOLDER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/ressources/");
+/// the scale of the landscape image
+const LANDSCAPE_SCALE: f32 = 2.0;
+
+lazy_static::lazy_static! {
+    /// the images shared by all the views (the games only hold the simulation)
+    pub static ref SPRITES: Sprites = Sprites::default();
+}
+
+/// the images of the game
+/// NOTE : there is no bending image, humain.png is squashed in the hitbox of the bent dinosaur
+#[derive(Debug, Clone)]
+pub struct Sprites {
+    pub dinosaur : Handle,
+    pub cactus : Handle,
+    pub rock : Handle,
+    pub pterodactyle : Handle,
+    pub landscape : Handle,
+    /// the size of the landscape image (to repeat it)
+    pub landscape_size : Size,
+}
+
+impl Default for Sprites {
+    fn default() -> Self {
+        let get_handle = |name : &str| Handle::from_path(format!("{}{}", SPRITE_FOLDER, name));
+        Self {
+            dinosaur : get_handle("humain.png"),
+            cactus : get_handle("cactus.png"),
+            rock : get_handle("rock.png"),
+            pterodactyle : get_handle("pterodactyle.png"),
+            landscape : get_handle("landscape.png"),
+            landscape_size : Size { width : 232.0, height : 95.0 },
+        }
+    }
+}
+
+impl Sprites {
+    /// get the image of the obstacle (the hole has none, it stays a rectangle)
+    pub fn get_obstacle_sprite(&self, type_ : &ObstacleEntityType) -> Option<&Handle> {
+        match type_ {
+            ObstacleEntityType::Cactus => Some(&self.cactus),
+            ObstacleEntityType::Rock => Some(&self.rock),
+            ObstacleEntityType::Pterodactyle | ObstacleEntityType::PterodactyleWithRock => Some(&self.pterodactyle),
+            ObstacleEntityType::Hole => None,
+        }
+    }
+}
+
+/// a canvas drawn over the sprites of a land (a game, or a race with many dinosaurs)
+pub trait SpriteScene<M> : canvas::Program<M, State = ()> {
+    /// the game of the landscape and of the obstacles
+    fn get_land(&self) -> &Game;
+
+    /// the dinosaurs drawn with the sprite
+    fn get_sprite_dinosaurs(&self) -> Vec<&Dinosaur>;
+
+    /// draw only the canvas (the hitboxes, or the end of the game)
+    fn hide_sprites(&self) -> bool;
+}
+
+/// the view of a game (or of a race) : the sprites, then the canvas of the scene over them
+pub struct GameView<'a, S> {
+    scene : &'a S,
+}
+
+impl<'a, S> GameView<'a, S> {
+    pub fn new(scene : &'a S) -> Self {
+        Self { scene }
+    }
+}
+
+/// draw the landscape and the sprites of the dinosaurs and the obstacles (in the game bounds)
+fn draw_sprites(renderer : &mut iced::Renderer, land : &Game, dinosaurs : &[&Dinosaur]) {
+    let params = land.get_params();
+    let sprites = &*SPRITES;
+
+    // the landscape scroll at the speed of the obstacles
+    let tile = Size {
+        width : sprites.landscape_size.width * LANDSCAPE_SCALE,
+        height : sprites.landscape_size.height * LANDSCAPE_SCALE,
+    };
+    let offset = (params.obstacle_speed * land.last_time_update) as f32 % tile.width;
+    let mut x = -offset;
+    while x < params.game_width as f32 {
+        renderer.draw(
+            sprites.landscape.clone(),
+            Rectangle::new(Point { x, y : params.game_height as f32 - tile.height }, tile)
+        );
+        x += tile.width;
+    }
+
+    // the dinosaurs (the image is squashed in the hitbox when it bends)
+    for dinosaur in dinosaurs {
+        renderer.draw(
+            sprites.dinosaur.clone(),
+            Rectangle::new(
+                get_opposite(params, Point { x : dinosaur.x as f32, y : dinosaur.y as f32 }, dinosaur.height as f32),
+                Size { width : dinosaur.width as f32, height : dinosaur.height as f32 }
+            )
+        );
+    }
+
+    for obstacle in &land.obstacles {
+        if let Some(sprite) = sprites.get_obstacle_sprite(&obstacle.type_) {
+            renderer.draw(
+                sprite.clone(),
+                Rectangle::new(
+                    get_opposite(params, Point { x : obstacle.x as f32, y : obstacle.y as f32 }, obstacle.height as f32),
+                    Size { width : obstacle.width as f32, height : obstacle.height as f32 }
+                )
+            );
+        }
+    }
+}
+
+impl<'a, M, S : SpriteScene<M>> Widget<M, iced::Renderer> for GameView<'a, S> {
+    fn width(&self) -> Length {
+        Length::Fixed(self.scene.get_land().get_params().game_width as f32)
+    }
+
+    fn height(&self) -> Length {
+        Length::Fixed(self.scene.get_land().get_params().game_height as f32)
+    }
+
+    fn layout(&self, _renderer: &iced::Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width()).height(self.height());
+        layout::Node::new(limits.resolve(Size::ZERO))
+    }
+
+    // give the events to the canvas of the scene
+    fn on_event(
+        &mut self,
+        _state: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &iced::Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, M>,
+    ) -> event::Status {
+        let canvas_event = match event {
+            Event::Mouse(mouse_event) => canvas::Event::Mouse(mouse_event),
+            Event::Touch(touch_event) => canvas::Event::Touch(touch_event),
+            Event::Keyboard(keyboard_event) => canvas::Event::Keyboard(keyboard_event),
+            _ => return event::Status::Ignored,
+        };
+
+        let (status, message) = self.scene.update(&mut (), canvas_event, layout.bounds(), Cursor::Available(cursor_position));
+        if let Some(message) = message {
+            shell.publish(message);
+        }
+        status
+    }
+
+    fn draw(
+        &self,
+        _state: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        renderer.with_translation(Vector::new(bounds.x, bounds.y), |renderer| {
+            if !self.scene.hide_sprites() {
+                draw_sprites(renderer, self.scene.get_land(), &self.scene.get_sprite_dinosaurs());
+            }
+            let geometries = self.scene.draw(&(), theme, bounds, Cursor::Available(cursor_position));
+            renderer.draw_primitive(Primitive::Group {
+                primitives : geometries.into_iter().map(Geometry::into_primitive).collect(),
+            });
+        });
+    }
+}
+
+impl<'a, M : 'a, S : SpriteScene<M>> From<GameView<'a, S>> for Element<'a, M> {
+    fn from(view: GameView<'a, S>) -> Self {
+        Element::new(view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced_native::image::Data;
+
+    use super::*;
+
+    #[test]
+    fn test_sprite_files_exist() {
+        let sprites = Sprites::default();
+        for handle in [&sprites.dinosaur, &sprites.cactus, &sprites.rock, &sprites.pterodactyle, &sprites.landscape] {
+            match handle.data() {
+                Data::Path(path) => assert!(path.exists(), "{} is missing", path.display()),
+                _ => panic!("the sprites are loaded from the ressources folder"),
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+
+use serde::{Serialize, Deserialize};
+
+use crate::brain::BrainScore;
+use crate::controller::Controller;
+use crate::error::{Error, Result};
+use crate::params::FitnessAggregation;
+use crate::selection::compare_brain_score;
+
+/// the name of the statistics file in the training folder (one json line per generation)
+pub const STATS_FILE_NAME: &str = "stats.jsonl";
+
+/// the statistics of one generation of the training
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct GenerationStats {
+    pub generation : u64,
+    pub land_seeds : Vec<String>,
+
+    pub best_score : f64,
+    pub mean_score : f64,
+    pub median_score : f64,
+    pub min_score : f64,
+
+    /// the energie of the best brain
+    pub best_energy : f64,
+    pub mean_energy : f64,
+
+    pub population_size : usize,
+    /// the mean number of neurone webs (or of layers of the neural networks, see Controller::get_size)
+    pub mean_nb_neurone_web : f64,
+    pub mean_nb_neurone : f64,
+
+    /// the time to run the whole generation (evaluation, selection, save and creation of the next generation, in seconds)
+    pub wall_time : f64,
+    /// the number of species after the selection (0 without speciation, set by the training)
+    #[serde(default)]
+    pub nb_species : usize,
+}
+
+impl GenerationStats {
+    /// compute the statistics of the scored generation (with the number of species after its speciation)
+    pub fn new<C : Controller>(generation : u64, land_seeds : &[String], scores : &[BrainScore<C>], nb_species : usize, wall_time : f64) -> Self {
+        let best = scores.iter().min_by(|a, b| compare_brain_score(a, b));
+        let population_size = scores.len();
+        let mean = |values : Vec<f64>| FitnessAggregation::Mean.aggregate_values(&values);
+        let fitness : Vec<f64> = scores.iter().map(|score| score.score).collect();
+
+        Self {
+            generation,
+            land_seeds : land_seeds.to_vec(),
+            best_score : best.map(|score| score.score).unwrap_or(0.0),
+            mean_score : mean(fitness.clone()),
+            median_score : FitnessAggregation::Median.aggregate_values(&fitness),
+            min_score : FitnessAggregation::Min.aggregate_values(&fitness),
+            best_energy : best.map(|score| score.energy).unwrap_or(0.0),
+            mean_energy : mean(scores.iter().map(|score| score.energy).collect()),
+            population_size,
+            mean_nb_neurone_web : mean(scores.iter().map(|score| score.brain.get_size().0 as f64).collect()),
+            mean_nb_neurone : mean(scores.iter().map(|score| score.brain.get_size().1 as f64).collect()),
+            wall_time,
+            nb_species,
+        }
+    }
+
+    /// load the statistics of the training folder (none if there is no statistics file)
+    /// NOTE : the invalid lines are skipped (the last one can be written by a running training)
+    pub fn load(folder_path : &str) -> Result<Vec<GenerationStats>> {
+        Ok(read_lines(folder_path)?
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// remove the statistics of the generations from the given one (they are run again when a training is resumed)
+    /// NOTE : the invalid lines are removed too
+    pub fn truncate(folder_path : &str, generation : u64) -> Result<()> {
+        let path = format!("{}{}", folder_path, STATS_FILE_NAME);
+        let lines = read_lines(folder_path)?;
+        let kept : Vec<&String> = lines.iter()
+            .filter(|line| serde_json::from_str::<GenerationStats>(line).is_ok_and(|stats| stats.generation < generation))
+            .collect();
+        if kept.len() == lines.len() {
+            return Ok(());
+        }
+        let content : String = kept.iter().map(|line| format!("{}\n", line)).collect();
+        fs::write(&path, content).map_err(|source| Error::Write { path, source })
+    }
+
+    /// add the statistics at the end of the statistics file of the training folder
+    pub fn append(&self, folder_path : &str) -> Result<()> {
+        let path = format!("{}{}", folder_path, STATS_FILE_NAME);
+        let line = serde_json::to_string(self).map_err(|source| Error::Write { path : path.clone(), source : source.into() })?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|source| Error::Write { path, source })
+    }
+}
+
+/// read the lines of the statistics file of the training folder (none if there is no statistics file)
+fn read_lines(folder_path : &str) -> Result<Vec<String>> {
+    let path = format!("{}{}", folder_path, STATS_FILE_NAME);
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(content.lines().map(String::from).collect()),
+        Err(source) if source.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(source) => Err(Error::Read { path, source }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::params::GameParameters;
+    use crate::utils::get_test_path;
+
+    use super::*;
+
+    #[test]
+    fn test_generation_stats() {
+        let params = GameParameters::new_default();
+        let scores = BrainScore::new_random_tests(&params, &[4.0, 10.0, 1.0, 6.0]);
+
+        let stats = GenerationStats::new(7, &["gra".to_string()], &scores, 2, 1.5);
+        assert_eq!(stats.generation, 7);
+        assert_eq!(stats.best_score, 10.0);
+        assert_eq!(stats.mean_score, 5.25);
+        assert_eq!(stats.median_score, 5.0);
+        assert_eq!(stats.min_score, 1.0);
+        assert_eq!(stats.best_energy, scores[1].brain.get_energie(&params));
+        assert_eq!(stats.population_size, 4);
+        assert_eq!(stats.nb_species, 2);
+        let nb_web : usize = scores.iter().map(|score| score.brain.neurone_web.len()).sum();
+        assert_eq!(stats.mean_nb_neurone_web, nb_web as f64 / 4.0);
+    }
+
+    #[test]
+    fn test_truncate_stats() {
+        let params = GameParameters::new_default();
+        let folder_path = get_test_path("stats/");
+        let _ = fs::remove_dir_all(&folder_path);
+        fs::create_dir(&folder_path).unwrap();
+        assert!(GenerationStats::load(&folder_path).unwrap().is_empty());
+
+        let scores = BrainScore::new_random_tests(&params, &[4.0, 10.0]);
+        for generation in 0..5 {
+            GenerationStats::new(generation, &["gra".to_string()], &scores, 0, 1.0).append(&folder_path).unwrap();
+        }
+        // the generations run again after a resume are removed
+        GenerationStats::truncate(&folder_path, 3).unwrap();
+        let stats = GenerationStats::load(&folder_path).unwrap();
+        assert_eq!(stats.iter().map(|stats| stats.generation).collect::<Vec<u64>>(), vec![0, 1, 2]);
+
+        fs::remove_dir_all(&folder_path).unwrap();
+    }
+}
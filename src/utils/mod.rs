@@ -2,6 +2,9 @@ use std::fs;
 
 use rand::{distributions::Uniform, Rng};
 use rand_pcg::Pcg64;
+use serde::Serialize;
+
+use crate::error::{Error, Result};
 
 /// convert a string to a [u8; 32] array
 /// NOTE : fill the array with zeroes if the string is shorter than 32 characters
@@ -47,6 +50,7 @@ pub fn get_scale_value(
 }
 
 /// check if 2 squares overlap
+#[allow(clippy::too_many_arguments)]
 pub fn check_collision(
     x1: f64,
     y1: f64,
@@ -82,10 +86,48 @@ pub fn remove_indexes<T>(vec : &mut Vec<T>, indexes : &Vec<usize>) {
         }
     }
 }
-/// get the max i of the brain file in the folder
-pub fn get_max_i(folder_path : &str) -> Option<u64> {
-    let max_i = fs::read_dir(folder_path)
-        .expect("Failed to read directory")
+/// uniform crossover of two vectors of genes :
+/// at each position shared by the two parents, the gene is taken from one parent (1/2 chance each),
+/// the positions only in the longest parent are kept with 1/2 chance
+pub fn uniform_crossover<T : Clone>(parent_a : &[T], parent_b : &[T], rng : &mut Pcg64) -> Vec<T> {
+    merge_crossover(parent_a, parent_b, rng, |gene_a, gene_b, rng| {
+        if rng.gen_bool(0.5) {
+            gene_a.clone()
+        }else{
+            gene_b.clone()
+        }
+    })
+}
+
+/// crossover of two vectors of genes where the genes at the same position are merged together,
+/// the positions only in the longest parent are kept with 1/2 chance (see uniform_crossover)
+pub fn merge_crossover<T : Clone>(parent_a : &[T], parent_b : &[T], rng : &mut Pcg64, mut merge : impl FnMut(&T, &T, &mut Pcg64) -> T) -> Vec<T> {
+    let mut child = Vec::new();
+    for i in 0..parent_a.len().max(parent_b.len()) {
+        match (parent_a.get(i), parent_b.get(i)) {
+            (Some(gene_a), Some(gene_b)) => child.push(merge(gene_a, gene_b, rng)),
+            (Some(gene), None) | (None, Some(gene)) => {
+                if rng.gen_bool(0.5) {
+                    child.push(gene.clone());
+                }
+            },
+            (None, None) => {}
+        }
+    }
+
+    child
+}
+
+/// get a path in the temporary folder for a test file (or folder), unique to the process to run the tests concurrently
+#[cfg(test)]
+pub fn get_test_path(name : &str) -> String {
+    format!("{}/chrome_offline_game_test_{}_{}", std::env::temp_dir().display(), std::process::id(), name)
+}
+
+/// get the indexes i of the brain files (brainI.json) in the folder, sorted
+pub fn get_brain_indexes(folder_path : &str) -> Result<Vec<u64>> {
+    let mut indexes : Vec<u64> = fs::read_dir(folder_path)
+        .map_err(|source| Error::Read { path : folder_path.to_string(), source })?
         .filter_map(|entry| {
             entry.ok()
                 .and_then(|dir_entry| dir_entry.file_name().to_str().map(String::from))
@@ -97,13 +139,22 @@ pub fn get_max_i(folder_path : &str) -> Option<u64> {
                     }
                 })
         })
-        .max();
+        .collect();
+    indexes.sort_unstable();
 
-    max_i
+    Ok(indexes)
+}
+
+/// save the value in a json file
+pub fn write_json<T : Serialize>(path : &str, value : &T) -> Result<()> {
+    let json = serde_json::to_string(value).map_err(|source| Error::Write { path : path.to_string(), source : source.into() })?;
+    fs::write(path, json).map_err(|source| Error::Write { path : path.to_string(), source })
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -182,6 +233,26 @@ mod tests {
         assert_eq!(vec, vec![1, 2, 4, 5, 7, 8, 10]);
     }
 
+    #[test]
+    fn test_uniform_crossover() {
+        let mut rng = Pcg64::from_seed([0; 32]);
+        let parent_a = vec![1, 2, 3, 4, 5, 6];
+        let parent_b = vec![10, 20, 30];
+        for _ in 0..100 {
+            let child = uniform_crossover(&parent_a, &parent_b, &mut rng);
+            assert!(child.len() >= parent_b.len());
+            assert!(child.len() <= parent_a.len());
+            // the shared positions come from one of the parents
+            for i in 0..parent_b.len() {
+                assert!(child[i] == parent_a[i] || child[i] == parent_b[i]);
+            }
+            // the other genes come from the longest parent, in order
+            for gene in &child[parent_b.len()..] {
+                assert!(parent_a[parent_b.len()..].contains(gene));
+            }
+        }
+    }
+
     #[test]
     fn test_remove_indexes_empty_vec() {
         let mut vec: Vec<i32> = vec![];